@@ -0,0 +1,464 @@
+//! Computer players: each `Engine` chooses moves for whoever's turn it is, on any board.
+//!
+//! `choose_move` asks an engine for a move. Minimax plays perfectly on small boards, with
+//! weaker `Difficulty` levels for people to beat; Monte Carlo tree search plays on boards too
+//! big to search through, for as long as its `Budget` allows.
+
+use std::fmt;
+use std::fmt::Formatter;
+
+use clap::ValueEnum;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::BitBoard;
+use crate::{Coordinates, Game, GameOutcome};
+
+pub use mcts::Budget;
+
+mod mcts;
+
+/// How the computer goes about choosing its moves.
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    /// Negamax search, playing as well as the difficulty allows; best on small boards.
+    Minimax(Difficulty),
+    /// Monte Carlo tree search, thinking for as long as the budget allows; works on any board.
+    MonteCarlo(Budget),
+}
+
+// describes the engine the way a player would pick it, e.g. "minimax, perfect"
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Minimax(difficulty) => write!(f, "minimax, {}", difficulty),
+            Self::MonteCarlo(Budget::Iterations(iterations)) => {
+                write!(f, "mcts, {} iterations", iterations)
+            }
+            Self::MonteCarlo(Budget::Time(duration)) => {
+                write!(f, "mcts, {} ms", duration.as_millis())
+            }
+        }
+    }
+}
+
+/// How well the minimax engine plays.
+#[derive(PartialEq, Clone, Copy, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    /// Plays any empty tile at random
+    Easy,
+    /// Takes a win or blocks a loss when one is a move away, but can't see forks coming
+    Medium,
+    /// Looks a few moves ahead
+    Hard,
+    /// Searches every possible continuation once few enough tiles are empty, playing like hard
+    /// until then; unbeatable on small boards
+    Perfect,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Easy => "easy",
+            Self::Medium => "medium",
+            Self::Hard => "hard",
+            Self::Perfect => "perfect",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Difficulty {
+    // how many moves ahead the Hard computer looks before guessing who's better off
+    const HARD_SEARCH_DEPTH: usize = 4;
+
+    /// The most empty tiles the Perfect computer searches every continuation from. Each extra
+    /// tile makes that search several times slower, taking it from well under a second to
+    /// minutes, so with more tiles empty than this it looks only as far ahead as Hard does.
+    pub const MAX_PERFECT_SEARCH_TILES: usize = 12;
+}
+
+// Scores for won positions are offset by this much, so that even the slowest win outranks
+// the most promising position `SearchBoard::evaluate` can describe.
+const WIN_SCORE: i32 = 1_000_000;
+
+// A copy of the game board's bitboard that's cheap to mutate, so we can play out
+// hypothetical moves during search without touching the real `Game`. Tiles are addressed by
+// their bitboard index (see `BitBoard`), and players by their index within `Game::players`.
+struct SearchBoard {
+    bits: BitBoard,
+    // every tile index, ordered from the center of the board outward; trying central moves
+    // first lets alpha-beta pruning cut off more of the search tree
+    tile_order: Vec<usize>,
+}
+
+impl SearchBoard {
+    fn from_game(game: &Game) -> Self {
+        let bits = game.board.bits.clone();
+        let num_rows = (bits.num_tiles() / bits.num_columns()) as isize;
+        let num_columns = bits.num_columns() as isize;
+        let distance_from_center = |tile_index: &usize| {
+            let indices = bits.indices(*tile_index);
+            // doubled, to keep everything in integers on boards with an even side length
+            (2 * indices.row as isize - (num_rows - 1)).abs()
+                + (2 * indices.column as isize - (num_columns - 1)).abs()
+        };
+        let mut tile_order = (0..bits.num_tiles()).collect::<Vec<usize>>();
+        tile_order.sort_by_key(distance_from_center);
+        Self { bits, tile_order }
+    }
+
+    fn num_players(&self) -> usize {
+        self.bits.num_players()
+    }
+
+    fn empty_tiles(&self) -> Vec<usize> {
+        let occupied_mask = self.bits.occupied_mask();
+        self.tile_order
+            .iter()
+            .copied()
+            .filter(|&tile_index| occupied_mask & (1 << tile_index) == 0)
+            .collect()
+    }
+
+    fn is_winning_move(&self, tile_index: usize, player_index: usize) -> bool {
+        self.bits.is_winning_move(tile_index, player_index)
+    }
+
+    // the first empty tile where the given player's mark would complete a winning line
+    fn find_winning_move(&mut self, player_index: usize) -> Option<usize> {
+        self.empty_tiles().into_iter().find(|&tile_index| {
+            self.bits.place(tile_index, player_index);
+            let is_winning_move = self.is_winning_move(tile_index, player_index);
+            self.bits.remove(tile_index, player_index);
+            is_winning_move
+        })
+    }
+
+    // A rough guess at how good the position is for the given player, for when we stop
+    // searching before the game is over: every winning line that only one player has marks
+    // in counts in that player's favor, and the more marks they have in it, the more it
+    // counts.
+    fn evaluate(&self, player_index: usize) -> i32 {
+        let mut score = 0;
+        for &mask in self.bits.win_masks() {
+            let mut occupiers = (0..self.num_players())
+                .filter(|&occupier_index| self.bits.player_mask(occupier_index) & mask != 0);
+            let (Some(occupier_index), None) = (occupiers.next(), occupiers.next()) else {
+                // nobody has a mark in this line yet, or it's already blocked
+                continue;
+            };
+            let num_marks = (self.bits.player_mask(occupier_index) & mask).count_ones() as i32;
+            let line_score = num_marks * num_marks;
+            if occupier_index == player_index {
+                score += line_score;
+            } else {
+                score -= line_score;
+            }
+        }
+        score
+    }
+
+    // Negamax search with alpha-beta pruning, scoring the position from the perspective of
+    // the player about to move. A win scores higher the sooner it happens (so we don't toy
+    // with an opponent we could beat right away), a loss scores lower the sooner it happens,
+    // and a draw scores zero. Stops `max_depth` moves ahead if given one, falling back on
+    // `evaluate` for positions that are still undecided by then.
+    //
+    // With more than two players, the search is "paranoid": it assumes every other player is
+    // out to beat `root_player_index` (the player we're choosing a move for), so it's really a
+    // two-sided search between them and everyone else. Scores are from the perspective of the
+    // mover's side, flipping sign only when the turn passes from one side to the other.
+    fn negamax(
+        &mut self,
+        player_index: usize,
+        root_player_index: usize,
+        max_depth: Option<usize>,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let empty_tiles = self.empty_tiles();
+        if empty_tiles.is_empty() {
+            return 0;
+        }
+        if max_depth == Some(0) {
+            let root_score = self.evaluate(root_player_index);
+            return if player_index == root_player_index {
+                root_score
+            } else {
+                -root_score
+            };
+        }
+        let next_player_index = (player_index + 1) % self.num_players();
+        let next_max_depth = max_depth.map(|depth| depth - 1);
+        // between two opponents of the root player the turn stays on the same side
+        let same_side = player_index != root_player_index && next_player_index != root_player_index;
+        let mut best_score = i32::MIN + 1;
+        for tile_index in empty_tiles.iter().copied() {
+            self.bits.place(tile_index, player_index);
+            let score = if self.is_winning_move(tile_index, player_index) {
+                WIN_SCORE + empty_tiles.len() as i32
+            } else if same_side {
+                self.negamax(
+                    next_player_index,
+                    root_player_index,
+                    next_max_depth,
+                    alpha,
+                    beta,
+                )
+            } else {
+                -self.negamax(
+                    next_player_index,
+                    root_player_index,
+                    next_max_depth,
+                    -beta,
+                    -alpha,
+                )
+            };
+            self.bits.remove(tile_index, player_index);
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    fn to_coordinates(&self, tile_index: usize) -> Coordinates {
+        Coordinates::from_indices(&self.bits.indices(tile_index)).unwrap()
+    }
+}
+
+/// Picks a move for the current turn's player using the given engine. Returns None if the
+/// game is already over, whether it was won or there are no empty tiles left.
+pub fn choose_move(game: &Game, engine: Engine) -> Option<Coordinates> {
+    if game.outcome() != GameOutcome::InProgress {
+        return None;
+    }
+    let mut board = SearchBoard::from_game(game);
+    let current_player = game.get_current_turn_player();
+    let player_index = game
+        .players
+        .iter()
+        .position(|player| player.number == current_player.number)?;
+    let tile_index = match engine {
+        Engine::Minimax(difficulty) => choose_minimax_move(&mut board, player_index, difficulty),
+        // random playouts are noisy enough to sometimes overlook a win (or a loss) that's just
+        // one move away, so we check for those directly first
+        Engine::MonteCarlo(budget) => board
+            .find_winning_move(player_index)
+            .or_else(|| board.find_winning_move((player_index + 1) % board.num_players()))
+            .or_else(|| mcts::search_best_move(&mut board, player_index, budget)),
+    }?;
+    Some(board.to_coordinates(tile_index))
+}
+
+fn choose_minimax_move(
+    board: &mut SearchBoard,
+    player_index: usize,
+    difficulty: Difficulty,
+) -> Option<usize> {
+    match difficulty {
+        Difficulty::Easy => board.empty_tiles().choose(&mut rand::rng()).copied(),
+        Difficulty::Medium => choose_medium_move(board, player_index),
+        Difficulty::Hard => {
+            search_best_move(board, player_index, Some(Difficulty::HARD_SEARCH_DEPTH))
+        }
+        Difficulty::Perfect if board.empty_tiles().len() > Difficulty::MAX_PERFECT_SEARCH_TILES => {
+            search_best_move(board, player_index, Some(Difficulty::HARD_SEARCH_DEPTH))
+        }
+        Difficulty::Perfect => search_best_move(board, player_index, None),
+    }
+}
+
+// Wins if possible, else blocks whoever moves next from winning, else plays at random. Only
+// ever looks one move ahead, so it walks right into forks.
+fn choose_medium_move(board: &mut SearchBoard, player_index: usize) -> Option<usize> {
+    let next_player_index = (player_index + 1) % board.num_players();
+    board
+        .find_winning_move(player_index)
+        .or_else(|| board.find_winning_move(next_player_index))
+        .or_else(|| board.empty_tiles().choose(&mut rand::rng()).copied())
+}
+
+// Searches for the best move by negamax, either through every possible continuation of the
+// game (which, with two players on a 3x3 board, plays perfectly) or only up to `max_depth`
+// moves ahead.
+fn search_best_move(
+    board: &mut SearchBoard,
+    player_index: usize,
+    max_depth: Option<usize>,
+) -> Option<usize> {
+    let next_player_index = (player_index + 1) % board.num_players();
+    let next_max_depth = max_depth.map(|depth| depth.saturating_sub(1));
+
+    let mut best_move: Option<(usize, i32)> = None;
+    for tile_index in board.empty_tiles() {
+        // only a strictly better score can change our choice, so that's all we search for
+        let alpha = best_move.map_or(i32::MIN + 1, |(_, score)| score);
+        board.bits.place(tile_index, player_index);
+        if board.is_winning_move(tile_index, player_index) {
+            return Some(tile_index);
+        }
+        let score = -board.negamax(
+            next_player_index,
+            player_index,
+            next_max_depth,
+            i32::MIN + 1,
+            -alpha,
+        );
+        board.bits.remove(tile_index, player_index);
+        if best_move.is_none_or(|(_, best_score)| score > best_score) {
+            best_move = Some((tile_index, score));
+        }
+    }
+    best_move.map(|(tile_index, _)| tile_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::GameSettings;
+
+    fn game_after(settings: GameSettings, moves: &str) -> Game {
+        let mut game = Game::new(settings).unwrap();
+        for input in moves.split_whitespace() {
+            game.play_move(Coordinates::from_user_input(input).unwrap())
+                .unwrap();
+        }
+        game
+    }
+
+    fn chosen_move(game: &Game, engine: Engine) -> String {
+        choose_move(game, engine).unwrap().to_string()
+    }
+
+    // plays the game out with each player's engine choosing their moves, returning the winner's
+    // player number, if anyone won
+    fn play_out(game: &mut Game, engines: &[Engine]) -> Option<u8> {
+        while game.outcome() == GameOutcome::InProgress {
+            let current_player = game.get_current_turn_player();
+            let engine = engines[usize::from(current_player.number - 1)];
+            game.play_move(choose_move(game, engine).unwrap()).unwrap();
+        }
+        match game.outcome() {
+            GameOutcome::Victory(winner) => Some(winner.number),
+            _ => None,
+        }
+    }
+
+    const PERFECT: Engine = Engine::Minimax(Difficulty::Perfect);
+
+    #[test]
+    fn perfect_never_loses_on_a_classic_board() {
+        for opponent in [Difficulty::Easy, Difficulty::Medium] {
+            for _ in 0..10 {
+                let mut game = Game::new(GameSettings::default()).unwrap();
+                let winner = play_out(&mut game, &[PERFECT, Engine::Minimax(opponent)]);
+                assert_ne!(winner, Some(2), "lost to {} moving second", opponent);
+                let mut game = Game::new(GameSettings::default()).unwrap();
+                let winner = play_out(&mut game, &[Engine::Minimax(opponent), PERFECT]);
+                assert_ne!(winner, Some(1), "lost to {} moving first", opponent);
+            }
+        }
+    }
+
+    #[test]
+    fn perfect_takes_a_win_and_blocks_a_loss() {
+        let game = game_after(GameSettings::default(), "A1 B1 A2 B2");
+        assert_eq!(chosen_move(&game, PERFECT), "A3");
+        let game = game_after(GameSettings::default(), "A1 B1 C3");
+        assert_eq!(chosen_move(&game, PERFECT), "B2");
+        let game = game_after(GameSettings::default(), "A1 B2 A2");
+        assert_eq!(chosen_move(&game, PERFECT), "A3");
+    }
+
+    #[test]
+    fn perfect_always_draws_against_itself() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        assert_eq!(play_out(&mut game, &[PERFECT, PERFECT]), None);
+    }
+
+    #[test]
+    fn no_move_once_the_game_is_over() {
+        let game = game_after(GameSettings::default(), "A1 B1 A2 B2 A3");
+        assert_eq!(choose_move(&game, PERFECT), None);
+    }
+
+    #[test]
+    fn easy_plays_any_empty_tile() {
+        let game = game_after(GameSettings::default(), "B2 A1 C3");
+        let legal_moves = game.legal_moves();
+        for _ in 0..20 {
+            let coords = choose_move(&game, Engine::Minimax(Difficulty::Easy)).unwrap();
+            assert!(legal_moves.contains(&coords), "{} is taken", coords);
+        }
+    }
+
+    #[test]
+    fn medium_and_hard_take_a_win_and_block_a_loss() {
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            let engine = Engine::Minimax(difficulty);
+            let game = game_after(GameSettings::default(), "A1 B1 A2 B2");
+            assert_eq!(chosen_move(&game, engine), "A3", "{}", difficulty);
+            let game = game_after(GameSettings::default(), "A1 B2 A2");
+            assert_eq!(chosen_move(&game, engine), "A3", "{}", difficulty);
+        }
+    }
+
+    #[test]
+    fn hard_blocks_a_loss_on_a_big_board() {
+        let settings = GameSettings {
+            num_rows: 6,
+            num_columns: 6,
+            win_length: Some(4),
+            ..GameSettings::default()
+        };
+        // X needs only D1 for four in a row
+        let game = game_after(settings, "A1 F6 B1 F4 C1");
+        assert_eq!(chosen_move(&game, Engine::Minimax(Difficulty::Hard)), "D1");
+    }
+
+    fn eight_by_eight() -> GameSettings {
+        GameSettings {
+            num_rows: 8,
+            num_columns: 8,
+            win_length: Some(4),
+            ..GameSettings::default()
+        }
+    }
+
+    #[test]
+    fn mcts_takes_a_win_and_blocks_a_loss_on_a_big_board() {
+        let engine = Engine::MonteCarlo(Budget::Iterations(200));
+        // X can finish row 2 at either end
+        let mut game = game_after(eight_by_eight(), "B2 H8 C2 H6 D2 A8");
+        game.play_move(choose_move(&game, engine).unwrap()).unwrap();
+        assert!(matches!(game.outcome(), GameOutcome::Victory(winner) if winner.number == 1));
+        // X needs only D1 for four in a row
+        let game = game_after(eight_by_eight(), "A1 H8 B1 H6 C1");
+        assert_eq!(chosen_move(&game, engine), "D1");
+    }
+
+    #[test]
+    fn mcts_always_simulates_at_least_one_game() {
+        let game = Game::new(eight_by_eight()).unwrap();
+        for budget in [Budget::Iterations(0), Budget::Time(Duration::ZERO)] {
+            let coords = choose_move(&game, Engine::MonteCarlo(budget));
+            assert!(coords.is_some(), "{:?}", budget);
+        }
+    }
+
+    #[test]
+    fn mcts_plays_a_big_board_to_the_end() {
+        let mut game = Game::new(eight_by_eight()).unwrap();
+        let engine = Engine::MonteCarlo(Budget::Iterations(50));
+        play_out(&mut game, &[engine, engine]);
+        assert!(game.outcome() != GameOutcome::InProgress);
+    }
+}
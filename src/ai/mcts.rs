@@ -0,0 +1,185 @@
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::SearchBoard;
+
+/// How long Monte Carlo tree search keeps thinking before it commits to a move.
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Budget {
+    /// Play out this many simulated games (at least one, even if this is zero).
+    Iterations(u32),
+    /// Play out as many simulated games as fit in this much time (at least one, however short).
+    Time(Duration),
+}
+
+// Balances exploring rarely-tried moves against exploiting moves that have done well so far;
+// this is the textbook value of sqrt(2), for rewards between 0 and 1.
+const EXPLORATION_WEIGHT: f64 = std::f64::consts::SQRT_2;
+
+// One position in the search tree, reached by `player_index` marking `tile_index` from the
+// position of its parent node (the root node, whose position is the real game board's, has
+// neither).
+struct Node {
+    tile_index: Option<usize>,
+    player_index: Option<usize>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_tiles: Vec<usize>,
+    // None while the game is still undecided in this node's position; Some(None) for a draw
+    outcome: Option<Option<usize>>,
+    num_visits: u32,
+    // running total of rewards for `player_index`, from every simulated game through this node
+    total_reward: f64,
+}
+
+impl Node {
+    fn new(
+        board: &SearchBoard,
+        tile_index: Option<usize>,
+        player_index: Option<usize>,
+        parent: Option<usize>,
+    ) -> Self {
+        let outcome = match (tile_index, player_index) {
+            (Some(tile_index), Some(player_index))
+                if board.is_winning_move(tile_index, player_index) =>
+            {
+                Some(Some(player_index))
+            }
+            _ if board.bits.is_full() => Some(None),
+            _ => None,
+        };
+        let mut untried_tiles = if outcome.is_none() {
+            board.empty_tiles()
+        } else {
+            vec![]
+        };
+        untried_tiles.shuffle(&mut rand::rng());
+        Self {
+            tile_index,
+            player_index,
+            parent,
+            children: vec![],
+            untried_tiles,
+            outcome,
+            num_visits: 0,
+            total_reward: 0.0,
+        }
+    }
+
+    // 1 for a win by this node's player, 0 for a loss and 1/2 for a draw
+    fn reward(&self, winner: Option<usize>) -> f64 {
+        match winner {
+            None => 0.5,
+            Some(winner) if Some(winner) == self.player_index => 1.0,
+            Some(_) => 0.0,
+        }
+    }
+
+    // the upper confidence bound (UCT) used to decide which child to explore next
+    fn upper_confidence_bound(&self, parent_num_visits: u32) -> f64 {
+        let num_visits = f64::from(self.num_visits);
+        self.total_reward / num_visits
+            + EXPLORATION_WEIGHT * (f64::from(parent_num_visits).ln() / num_visits).sqrt()
+    }
+}
+
+// Picks a move for `player_index` by simulating random games from the current position,
+// steering more and more simulations toward whichever moves have done well so far, until the
+// budget runs out. Returns the move that was simulated the most.
+pub(super) fn search_best_move(
+    board: &mut SearchBoard,
+    player_index: usize,
+    budget: Budget,
+) -> Option<usize> {
+    let mut nodes = vec![Node::new(board, None, None, None)];
+    // each simulation plays out on the same board, reset to the real game's position between
+    // simulations; that's just a couple of integers to copy, so it's cheap
+    let starting_bits = board.bits.clone();
+    let started_at = Instant::now();
+    let mut num_iterations = 0;
+    // however small the budget, a move can't be chosen without simulating at least one game
+    while match budget {
+        Budget::Iterations(max_iterations) => num_iterations < max_iterations.max(1),
+        Budget::Time(max_duration) => num_iterations == 0 || started_at.elapsed() < max_duration,
+    } {
+        num_iterations += 1;
+        board.bits.copy_tiles_from(&starting_bits);
+        let mut node_index = 0;
+        let mut next_player_index = player_index;
+
+        // selection: walk down through fully-explored nodes, following the most promising child
+        while nodes[node_index].untried_tiles.is_empty() && !nodes[node_index].children.is_empty() {
+            let parent_num_visits = nodes[node_index].num_visits;
+            node_index = *nodes[node_index]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let a_bound = nodes[a].upper_confidence_bound(parent_num_visits);
+                    let b_bound = nodes[b].upper_confidence_bound(parent_num_visits);
+                    a_bound.total_cmp(&b_bound)
+                })
+                .unwrap();
+            let tile_index = nodes[node_index].tile_index.unwrap();
+            board.bits.place(tile_index, next_player_index);
+            next_player_index = (next_player_index + 1) % board.num_players();
+        }
+
+        // expansion: add one not-yet-tried move as a new child node
+        if let Some(tile_index) = nodes[node_index].untried_tiles.pop() {
+            board.bits.place(tile_index, next_player_index);
+            let child = Node::new(
+                board,
+                Some(tile_index),
+                Some(next_player_index),
+                Some(node_index),
+            );
+            nodes.push(child);
+            let child_index = nodes.len() - 1;
+            nodes[node_index].children.push(child_index);
+            node_index = child_index;
+            next_player_index = (next_player_index + 1) % board.num_players();
+        }
+
+        // simulation: unless the game is already over, play it out with random moves
+        let winner = match nodes[node_index].outcome {
+            Some(winner) => winner,
+            None => board.play_out_randomly(next_player_index),
+        };
+
+        // backpropagation: credit the result to every node we passed through
+        let mut maybe_node_index = Some(node_index);
+        while let Some(node_index) = maybe_node_index {
+            let node = &mut nodes[node_index];
+            node.num_visits += 1;
+            node.total_reward += node.reward(winner);
+            maybe_node_index = node.parent;
+        }
+    }
+
+    nodes[0]
+        .children
+        .iter()
+        .max_by_key(|&&child_index| nodes[child_index].num_visits)
+        .and_then(|&child_index| nodes[child_index].tile_index)
+}
+
+impl SearchBoard {
+    // Fills the board's empty tiles in random order, taking turns starting with the given
+    // player, until somebody wins or the board is full. Returns the winner, or None for a draw.
+    fn play_out_randomly(&mut self, first_player_index: usize) -> Option<usize> {
+        let mut empty_tiles = self.bits.empty_tiles().collect::<Vec<usize>>();
+        empty_tiles.shuffle(&mut rand::rng());
+        let mut player_index = first_player_index;
+        for tile_index in empty_tiles {
+            self.bits.place(tile_index, player_index);
+            if self.is_winning_move(tile_index, player_index) {
+                return Some(player_index);
+            }
+            player_index = (player_index + 1) % self.num_players();
+        }
+        None
+    }
+}
@@ -0,0 +1,279 @@
+use std::rc::Rc;
+
+use crate::Indices;
+
+// A compact representation of which player occupies each tile: one u64 per player, with bit
+// `row * num_columns + column` set for each tile that player occupies. Boards are at most 8x8,
+// so every tile fits in a single u64. Win detection is just a handful of bitwise ANDs against
+// masks computed once up front, and copying a board means copying a few integers -- which is
+// what lets the computer players simulate huge numbers of positions.
+#[derive(Clone)]
+pub struct BitBoard {
+    num_rows: usize,
+    num_columns: usize,
+    player_masks: Vec<u64>,
+    // the geometry below never changes once a board is created, so every copy of a board
+    // shares it rather than duplicating it
+    win_masks: Rc<[u64]>,
+    win_masks_by_tile: Rc<[Vec<u64>]>,
+}
+
+impl BitBoard {
+    pub fn new(num_rows: usize, num_columns: usize, win_length: usize, num_players: usize) -> Self {
+        let win_masks = Self::build_win_masks(num_rows, num_columns, win_length);
+        let win_masks_by_tile = (0..num_rows * num_columns)
+            .map(|tile_index| {
+                win_masks
+                    .iter()
+                    .copied()
+                    .filter(|&mask| mask & (1 << tile_index) != 0)
+                    .collect::<Vec<u64>>()
+            })
+            .collect::<Vec<Vec<u64>>>();
+        Self {
+            num_rows,
+            num_columns,
+            player_masks: vec![0; num_players],
+            win_masks: win_masks.into(),
+            win_masks_by_tile: win_masks_by_tile.into(),
+        }
+    }
+
+    // Builds a mask for every horizontal, vertical and diagonal segment of `win_length` tiles
+    // that fits on the board. On a square board where `win_length` equals the board's size,
+    // this is just its rows, columns and two full-length diagonals.
+    fn build_win_masks(num_rows: usize, num_columns: usize, win_length: usize) -> Vec<u64> {
+        // (row step, column step) for rightward, downward, down-right and up-right lines
+        const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (-1, 1)];
+        let mut win_masks: Vec<u64> = Vec::new();
+        for row_index in 0..num_rows {
+            for column_index in 0..num_columns {
+                for (row_step, column_step) in DIRECTIONS {
+                    let mask = (0..win_length as isize).try_fold(0, |mask, step| {
+                        let row = row_index as isize + row_step * step;
+                        let column = column_index as isize + column_step * step;
+                        let in_bounds = (0..num_rows as isize).contains(&row)
+                            && (0..num_columns as isize).contains(&column);
+                        in_bounds
+                            .then(|| mask | 1 << (row as usize * num_columns + column as usize))
+                    });
+                    // a line that would run off the edge of the board can't be won
+                    if let Some(mask) = mask {
+                        win_masks.push(mask);
+                    }
+                }
+            }
+        }
+        win_masks
+    }
+
+    pub fn num_tiles(&self) -> usize {
+        self.num_rows * self.num_columns
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.player_masks.len()
+    }
+
+    // None if the indices are off the board
+    pub fn tile_index(&self, indices: &Indices) -> Option<usize> {
+        (indices.row < self.num_rows && indices.column < self.num_columns)
+            .then_some(indices.row * self.num_columns + indices.column)
+    }
+
+    pub fn indices(&self, tile_index: usize) -> Indices {
+        Indices {
+            row: tile_index / self.num_columns,
+            column: tile_index % self.num_columns,
+        }
+    }
+
+    pub fn player_mask(&self, player_index: usize) -> u64 {
+        self.player_masks[player_index]
+    }
+
+    pub fn occupied_mask(&self) -> u64 {
+        self.player_masks
+            .iter()
+            .fold(0, |mask, player_mask| mask | player_mask)
+    }
+
+    pub fn full_mask(&self) -> u64 {
+        u64::MAX >> (u64::BITS as usize - self.num_tiles())
+    }
+
+    // index of the player occupying the given tile, if any
+    pub fn occupier(&self, tile_index: usize) -> Option<usize> {
+        self.player_masks
+            .iter()
+            .position(|player_mask| player_mask & (1 << tile_index) != 0)
+    }
+
+    pub fn empty_tiles(&self) -> impl Iterator<Item = usize> {
+        tile_indices(self.full_mask() & !self.occupied_mask())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied_mask() == self.full_mask()
+    }
+
+    pub fn place(&mut self, tile_index: usize, player_index: usize) {
+        self.player_masks[player_index] |= 1 << tile_index;
+    }
+
+    pub fn remove(&mut self, tile_index: usize, player_index: usize) {
+        self.player_masks[player_index] &= !(1 << tile_index);
+    }
+
+    // Copies just the tile occupation from another board of the same size, which is much
+    // cheaper than a full clone.
+    pub fn copy_tiles_from(&mut self, other: &BitBoard) {
+        self.player_masks.copy_from_slice(&other.player_masks);
+    }
+
+    // whether the given player's mark on the given tile completes any winning line
+    pub fn is_winning_move(&self, tile_index: usize, player_index: usize) -> bool {
+        let player_mask = self.player_masks[player_index];
+        // a line is complete when none of its tiles are missing from the player's mask
+        self.win_masks_by_tile[tile_index]
+            .iter()
+            .any(|&mask| mask & !player_mask == 0)
+    }
+
+    // the first winning line any player has completed, as (player index, line mask)
+    pub fn find_completed_line(&self) -> Option<(usize, u64)> {
+        self.win_masks.iter().find_map(|&mask| {
+            self.player_masks
+                .iter()
+                .position(|player_mask| player_mask & mask == mask)
+                .map(|player_index| (player_index, mask))
+        })
+    }
+
+    pub fn win_masks(&self) -> &[u64] {
+        &self.win_masks
+    }
+}
+
+// the index of every set bit in the mask, lowest first
+pub fn tile_indices(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let tile_index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(tile_index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the mask of the given (row, column) tiles on a board with `num_columns` columns
+    fn mask(num_columns: usize, tiles: &[(usize, usize)]) -> u64 {
+        tiles.iter().fold(0, |mask, &(row, column)| {
+            mask | 1 << (row * num_columns + column)
+        })
+    }
+
+    #[test]
+    fn classic_board_has_rows_columns_and_both_diagonals() {
+        let win_masks = BitBoard::build_win_masks(3, 3, 3);
+        assert_eq!(win_masks.len(), 8);
+        for line in [
+            [(0, 0), (0, 1), (0, 2)],
+            [(2, 0), (2, 1), (2, 2)],
+            [(0, 1), (1, 1), (2, 1)],
+            [(0, 0), (1, 1), (2, 2)],
+            [(2, 0), (1, 1), (0, 2)],
+        ] {
+            assert!(win_masks.contains(&mask(3, &line)));
+        }
+    }
+
+    #[test]
+    fn shorter_win_length_includes_off_main_diagonals() {
+        let win_masks = BitBoard::build_win_masks(4, 4, 3);
+        // two segments along each of 4 rows and 4 columns, plus 4 diagonals each way
+        assert_eq!(win_masks.len(), 24);
+        for line in [
+            [(0, 1), (1, 2), (2, 3)],
+            [(1, 0), (2, 1), (3, 2)],
+            [(3, 1), (2, 2), (1, 3)],
+            [(2, 0), (1, 1), (0, 2)],
+            [(1, 1), (1, 2), (1, 3)],
+        ] {
+            assert!(win_masks.contains(&mask(4, &line)));
+        }
+        // lines shorter than the win length don't count
+        assert!(!win_masks.contains(&mask(4, &[(0, 2), (1, 3)])));
+    }
+
+    #[test]
+    fn rectangular_board_lines_stay_within_their_rows() {
+        let win_masks = BitBoard::build_win_masks(3, 5, 3);
+        // 3 segments along each of 3 rows, 1 along each of 5 columns, and 3 diagonals each way
+        assert_eq!(win_masks.len(), 20);
+        assert!(win_masks.contains(&mask(5, &[(0, 2), (1, 3), (2, 4)])));
+        assert!(win_masks.contains(&mask(5, &[(2, 2), (1, 3), (0, 4)])));
+        // consecutive bits that wrap from the end of one row onto the next aren't a line
+        assert!(!win_masks.contains(&mask(5, &[(0, 3), (0, 4), (1, 0)])));
+        assert!(win_masks
+            .iter()
+            .all(|&mask| mask.count_ones() == 3 && mask < 1 << 15));
+    }
+
+    #[test]
+    fn full_size_board_uses_every_bit() {
+        let mut board = BitBoard::new(8, 8, 8, 2);
+        assert_eq!(board.full_mask(), u64::MAX);
+        assert_eq!(board.empty_tiles().count(), 64);
+        board.place(63, 1);
+        assert_eq!(board.occupier(63), Some(1));
+        assert_eq!(board.empty_tiles().last(), Some(62));
+        for tile_index in 0..63 {
+            board.place(tile_index, tile_index % 2);
+        }
+        assert!(board.is_full());
+        assert_eq!(board.empty_tiles().count(), 0);
+    }
+
+    #[test]
+    fn small_board_full_mask_covers_only_its_tiles() {
+        let board = BitBoard::new(2, 3, 2, 2);
+        assert_eq!(board.full_mask(), 0b11_1111);
+    }
+
+    #[test]
+    fn winning_move_completes_a_line() {
+        let mut board = BitBoard::new(3, 3, 3, 2);
+        board.place(0, 0);
+        board.place(4, 0);
+        board.place(2, 1);
+        assert!(!board.is_winning_move(4, 0));
+        assert_eq!(board.find_completed_line(), None);
+        board.place(8, 0);
+        assert!(board.is_winning_move(8, 0));
+        assert!(!board.is_winning_move(8, 1));
+        assert_eq!(
+            board.find_completed_line(),
+            Some((0, mask(3, &[(0, 0), (1, 1), (2, 2)])))
+        );
+        board.remove(4, 0);
+        assert!(!board.is_winning_move(8, 0));
+        assert_eq!(board.find_completed_line(), None);
+    }
+
+    #[test]
+    fn tile_indices_lists_set_bits_lowest_first() {
+        assert_eq!(tile_indices(0).count(), 0);
+        assert_eq!(tile_indices(0b1010_0001).collect::<Vec<_>>(), vec![0, 5, 7]);
+        assert_eq!(tile_indices(1 << 63 | 1).collect::<Vec<_>>(), vec![0, 63]);
+    }
+}
@@ -0,0 +1,439 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use tic_tac_toe::ai::{Budget, Difficulty, Engine};
+use tic_tac_toe::{net, Game, GameSettings, PlayerColor, PlayerKind};
+
+// how many games the Monte Carlo engine simulates per move, unless told otherwise
+const DEFAULT_MCTS_ITERATIONS: u32 = 20_000;
+
+#[derive(Parser)]
+#[command(version, about = "Tic-tac-toe at the command line.")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // lets `tic-tac-toe --size 5` work as shorthand for `tic-tac-toe play --size 5`
+    #[command(flatten)]
+    pub play: PlayArgs,
+}
+
+impl Cli {
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Play(self.play))
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play a new game (the default when no subcommand is given)
+    Play(PlayArgs),
+    /// Step through a recorded or saved game, one move at a time
+    Replay(ReplayArgs),
+    /// Host a game for another player to join over the network, playing as player 1; only
+    /// the host adds the game to its stats
+    Host(HostArgs),
+    /// Join a game hosted over the network, playing as player 2
+    Join(GuestArgs),
+    /// Watch a game hosted over the network, without playing
+    Watch(JoinArgs),
+    /// Serve games over a local HTTP API, for web frontends and bots
+    Serve(ServeArgs),
+    /// Show every player's record and rating across all the games they've finished
+    Stats(StatsArgs),
+    /// Run a tournament between people and computer players, with a table of standings
+    Tournament(TournamentArgs),
+}
+
+// the settings for a new game, shared by every subcommand that starts one
+#[derive(Args)]
+pub struct SettingsArgs {
+    #[command(flatten)]
+    pub board: BoardArgs,
+
+    /// Each player's mark, in order, separated by commas; give three or more for a game with
+    /// that many players
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_values_t = ['X', 'O'],
+        value_parser = parse_mark
+    )]
+    pub marks: Vec<char>,
+
+    /// Each player's name, in order, separated by commas [default: "Player 1" and so on]
+    #[arg(short, long, value_delimiter = ',')]
+    pub names: Vec<String>,
+
+    /// Each player's color, in order, separated by commas [default: a different one for each]
+    #[arg(short, long, value_enum, value_delimiter = ',')]
+    pub colors: Vec<PlayerColor>,
+
+    /// Number of the player who moves first, or "random" to let chance decide
+    #[arg(short, long, default_value = "1", value_parser = parse_first_player)]
+    pub first: FirstPlayer,
+}
+
+// the shape of the board and how many in a row wins, for anything that sets up games
+#[derive(Args)]
+pub struct BoardArgs {
+    /// Number of rows and columns on the game board
+    #[arg(short, long, default_value_t = 3, value_parser = parse_board_size)]
+    pub size: usize,
+
+    /// Number of rows on the game board, if different from its number of columns
+    #[arg(long, value_parser = parse_board_size)]
+    pub rows: Option<usize>,
+
+    /// Number of columns on the game board, if different from its number of rows
+    #[arg(long, value_parser = parse_board_size)]
+    pub columns: Option<usize>,
+
+    /// Number of marks in a row needed to win [default: the board's shorter side]
+    #[arg(short = 'k', long)]
+    pub win_length: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct PlayArgs {
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+
+    /// Who is playing
+    #[arg(long, value_enum, default_value_t = GameMode::Local)]
+    pub mode: GameMode,
+
+    /// How the computer chooses its moves, when there is one [default: minimax on boards of up
+    /// to 12 tiles, mcts on bigger ones]
+    #[arg(short, long, value_enum)]
+    pub engine: Option<EngineKind>,
+
+    /// How well the minimax engine plays [default: perfect]
+    #[arg(short, long, value_enum)]
+    pub difficulty: Option<Difficulty>,
+
+    /// Number of games the Monte Carlo engine simulates per move [default: 20000]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub iterations: Option<u32>,
+
+    /// Milliseconds the Monte Carlo engine spends per move, instead of a set number of games
+    #[arg(
+        long,
+        conflicts_with = "iterations",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub think_time: Option<u64>,
+
+    /// Resume a game saved with the `save` command, instead of starting a new one
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["size", "rows", "columns", "win_length", "marks", "names", "colors", "first", "mode"]
+    )]
+    pub load: Option<PathBuf>,
+
+    /// Replay a game from a file of game records, then carry on playing if it isn't over
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["size", "rows", "columns", "win_length", "marks", "names", "colors", "first", "mode", "load"]
+    )]
+    pub import: Option<PathBuf>,
+
+    /// Which game to import, when the file holds more than one
+    #[arg(
+        long,
+        default_value_t = 1,
+        requires = "import",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub game_number: usize,
+
+    /// Add a record of the game to this file once it's over
+    #[arg(short, long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Play a match of up to this many games, taking turns moving first; it ends early once
+    /// someone has won more games than anyone else can catch up with
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub best_of: Option<u16>,
+
+    /// Play in a full-screen interface, choosing tiles with the arrow keys
+    #[arg(short, long)]
+    pub tui: bool,
+
+    #[command(flatten)]
+    pub stats: RecordStatsArgs,
+}
+
+// where (and whether) to keep stats, for every subcommand that plays a game
+#[derive(Args)]
+pub struct RecordStatsArgs {
+    /// Leave the game out of the stats shown by the `stats` subcommand
+    #[arg(long)]
+    pub no_stats: bool,
+
+    /// File to keep stats in [default: stats.json in the tic-tac-toe data directory]
+    #[arg(long, value_name = "FILE", conflicts_with = "no_stats")]
+    pub stats_file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct TournamentArgs {
+    /// A participant: just a name for a person, or NAME=ENGINE for the computer, where ENGINE
+    /// is easy, medium, hard or perfect (for minimax) or mcts (optionally mcts:ITERATIONS);
+    /// repeat for every participant, e.g. `-p Ada -p Deep=perfect -p Monty=mcts:5000`
+    #[arg(short, long = "participant", value_name = "PARTICIPANT", value_parser = parse_participant)]
+    pub participants: Vec<Participant>,
+
+    /// How participants are paired up
+    #[arg(long, value_enum, default_value_t = TournamentFormat::RoundRobin)]
+    pub format: TournamentFormat,
+
+    /// Number of rounds in a Swiss tournament [default: enough to find a clear winner]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub rounds: Option<u16>,
+
+    /// Number of games each pairing plays, taking turns moving first
+    #[arg(short, long, default_value_t = 2, value_parser = clap::value_parser!(u16).range(1..))]
+    pub games_per_pairing: u16,
+
+    #[command(flatten)]
+    pub board: BoardArgs,
+
+    #[command(flatten)]
+    pub stats: RecordStatsArgs,
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// File stats are kept in [default: stats.json in the tic-tac-toe data directory]
+    #[arg(long, value_name = "FILE")]
+    pub stats_file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// File of game records (see `--record`), or a game saved with the `save` command
+    pub file: PathBuf,
+
+    /// Which game to replay, when the file holds more than one
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub game_number: usize,
+}
+
+#[derive(Args)]
+pub struct HostArgs {
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+
+    /// Port to listen for the other player on
+    #[arg(short, long, default_value_t = net::DEFAULT_PORT)]
+    pub port: u16,
+
+    #[command(flatten)]
+    pub stats: RecordStatsArgs,
+}
+
+#[derive(Args)]
+pub struct JoinArgs {
+    /// Address of the host, e.g. 192.168.1.20:7878
+    #[arg(default_value_t = format!("localhost:{}", net::DEFAULT_PORT))]
+    pub address: String,
+}
+
+#[derive(Args)]
+pub struct GuestArgs {
+    #[command(flatten)]
+    pub join: JoinArgs,
+
+    /// What to call you in the game [default: "Player 2"]
+    #[arg(short, long)]
+    pub name: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Port to serve the API on
+    #[arg(short, long, default_value_t = 8080)]
+    pub port: u16,
+}
+
+impl SettingsArgs {
+    // computer players aren't covered by these settings; they're left at their defaults
+    pub fn game_settings(&self) -> GameSettings {
+        GameSettings {
+            marks: self.marks.clone(),
+            names: self.names.clone(),
+            colors: self.colors.clone(),
+            first_player_number: match self.first {
+                FirstPlayer::Number(number) => number,
+                FirstPlayer::Random => rand::random_range(1..=self.marks.len() as u8),
+            },
+            ..self.board.game_settings()
+        }
+    }
+}
+
+impl BoardArgs {
+    // everything else is left at its default
+    pub fn game_settings(&self) -> GameSettings {
+        GameSettings {
+            num_rows: self.num_rows(),
+            num_columns: self.num_columns(),
+            win_length: self.win_length,
+            ..GameSettings::default()
+        }
+    }
+
+    fn num_rows(&self) -> usize {
+        self.rows.unwrap_or(self.size)
+    }
+
+    fn num_columns(&self) -> usize {
+        self.columns.unwrap_or(self.size)
+    }
+}
+
+impl PlayArgs {
+    pub fn engine(&self) -> Engine {
+        let board = &self.settings.board;
+        // asking for a difficulty or a search budget picks the engine it's meant for; otherwise
+        // minimax plays wherever it can search every continuation from the first move, and
+        // Monte Carlo tree search plays on anything bigger
+        let engine = self.engine.unwrap_or(if self.difficulty.is_some() {
+            EngineKind::Minimax
+        } else if self.iterations.is_some() || self.think_time.is_some() {
+            EngineKind::Mcts
+        } else if board.num_rows() * board.num_columns() <= Difficulty::MAX_PERFECT_SEARCH_TILES {
+            EngineKind::Minimax
+        } else {
+            EngineKind::Mcts
+        });
+        match engine {
+            EngineKind::Minimax => Engine::Minimax(self.difficulty.unwrap_or(Difficulty::Perfect)),
+            EngineKind::Mcts => Engine::MonteCarlo(match self.think_time {
+                Some(millis) => Budget::Time(Duration::from_millis(millis)),
+                None => Budget::Iterations(self.iterations.unwrap_or(DEFAULT_MCTS_ITERATIONS)),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FirstPlayer {
+    Number(u8),
+    Random,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub kind: PlayerKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TournamentFormat {
+    /// Everyone plays everyone else once
+    RoundRobin,
+    /// Each round pairs up participants with similar scores, avoiding rematches where possible
+    Swiss,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GameMode {
+    /// People taking turns at the same keyboard
+    Local,
+    /// One person (player 1) against the computer (every other player)
+    Computer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EngineKind {
+    /// Minimax search; unbeatable on small boards at its highest difficulty
+    Minimax,
+    /// Monte Carlo tree search; plays well on boards of any size
+    Mcts,
+}
+
+fn parse_board_size(input: &str) -> Result<usize, String> {
+    let size = usize::from_str(input).map_err(|_| format!("'{}' is not a number.", input))?;
+    if !(Game::MIN_NUM_ROWS_OR_COLUMNS..=Game::MAX_NUM_ROWS_OR_COLUMNS).contains(&size) {
+        return Err(format!(
+            "Number of rows/columns on game board must be between {} and {}.",
+            Game::MIN_NUM_ROWS_OR_COLUMNS,
+            Game::MAX_NUM_ROWS_OR_COLUMNS
+        ));
+    }
+    Ok(size)
+}
+
+fn parse_first_player(input: &str) -> Result<FirstPlayer, String> {
+    if input.eq_ignore_ascii_case("random") {
+        return Ok(FirstPlayer::Random);
+    }
+    u8::from_str(input)
+        .map(FirstPlayer::Number)
+        .map_err(|_| format!("'{}' is neither a player number nor 'random'.", input))
+}
+
+fn parse_participant(input: &str) -> Result<Participant, String> {
+    let (name, engine) = match input.split_once('=') {
+        Some((name, engine)) => (name.trim(), Some(engine.trim().to_lowercase())),
+        None => (input.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(format!("'{}' has no name for the participant.", input));
+    }
+    let kind = match engine.as_deref() {
+        None => PlayerKind::Human,
+        Some("mcts") => PlayerKind::Computer(Engine::MonteCarlo(Budget::Iterations(
+            DEFAULT_MCTS_ITERATIONS,
+        ))),
+        Some(engine) => match engine.split_once(':') {
+            Some(("mcts", iterations)) => {
+                let iterations = u32::from_str(iterations)
+                    .ok()
+                    .filter(|&iterations| iterations > 0)
+                    .ok_or_else(|| {
+                        format!("'{}' is not a positive number of iterations.", iterations)
+                    })?;
+                PlayerKind::Computer(Engine::MonteCarlo(Budget::Iterations(iterations)))
+            }
+            _ => PlayerKind::Computer(Engine::Minimax(
+                Difficulty::from_str(engine, true).map_err(|_| {
+                    format!(
+                        "'{}' is not an engine; try easy, medium, hard, perfect or mcts.",
+                        engine
+                    )
+                })?,
+            )),
+        },
+    };
+    Ok(Participant {
+        name: name.to_string(),
+        kind,
+    })
+}
+
+fn parse_mark(input: &str) -> Result<char, String> {
+    let mut chars = input.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(mark), None) if !mark.is_whitespace() => Ok(mark),
+        _ => Err(format!(
+            "'{}' is not a valid mark; marks must be a single visible character.",
+            input
+        )),
+    }
+}
@@ -0,0 +1,305 @@
+//! Pluggable sources of moves, so any mix of people, engines and scripts can play a `Game`.
+//!
+//! A `PlayerController` is asked for a move whenever it's its player's turn. `play_out` plays a
+//! game through to the end with one controller per player, and `controller_for` picks the
+//! usual controller for a `PlayerKind`. Interactive frontends ask for a `Turn` instead, which
+//! lets people at the keyboard undo moves, save the game or quit as well.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use rand::seq::IndexedRandom;
+
+use crate::ai::{self, Engine};
+use crate::{Coordinates, Game, GameOutcome, Notification, NotificationType, PlayerKind};
+
+/// What a player does with their turn.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Turn {
+    /// Places the player's mark on the given tile. A person's move might not be legal, e.g. if
+    /// they mistyped it, in which case the frontend lets them try again.
+    Move(Coordinates),
+    /// Takes back moves until it's a person's turn again.
+    Undo,
+    /// Replays taken-back moves until it's a person's turn again.
+    Redo,
+    /// Saves the game to the given file, or to the frontend's default file if None.
+    Save(Option<PathBuf>),
+    /// Stops playing, whether or not the game is over.
+    Quit,
+}
+
+/// Something that chooses moves for one of a game's players.
+pub trait PlayerController {
+    /// Chooses a move for the current turn's player, given the game so far. The move should be
+    /// legal (see `Game::legal_moves`); `play_out` fails on any that isn't.
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates>;
+
+    /// Decides what to do with the current turn. By default, this is just to play the move
+    /// `choose_move` chooses; controllers for people can offer them more than that.
+    fn take_turn(&mut self, game: &Game) -> Result<Turn> {
+        self.choose_move(game).map(Turn::Move)
+    }
+
+    /// Whether a person is making this controller's choices. Frontends step back through undone
+    /// moves until it's a person's turn, and only let people off with illegal moves.
+    fn is_person(&self) -> bool {
+        false
+    }
+}
+
+/// A person at the terminal, typing their moves in on standard input. When asked for a move,
+/// keeps asking until they enter a legal one; when asked for a turn, they can also type "save"
+/// (optionally followed by a file path), "undo" or "redo". Fails if the input runs out.
+pub struct StdinController;
+
+/// Plays any legal move, at random.
+pub struct RandomController;
+
+/// Plays whatever the given engine from the `ai` module chooses.
+pub struct EngineController(pub Engine);
+
+/// Plays a fixed list of moves in order, e.g. to replay a game or set up a position, and fails
+/// once it runs out of moves.
+pub struct ScriptedController {
+    moves: VecDeque<Coordinates>,
+}
+
+impl StdinController {
+    // Asks the current player to enter their move (or one of the given commands), returning
+    // whatever they typed.
+    fn prompt(game: &Game, commands: &[&str]) -> Result<String> {
+        let current_player = game.get_current_turn_player();
+        let commands_hint = if commands.is_empty() {
+            String::new()
+        } else {
+            format!(" (or {})", commands.join("/"))
+        };
+        print!(
+            "{}, enter coordinates to place your {}{}: ",
+            current_player.to_string().bold(),
+            current_player.colored_mark(),
+            commands_hint
+        );
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(anyhow!("Reached end of input before the game was over."));
+        }
+        Ok(input)
+    }
+
+    // Shows what was wrong with the player's input, just above the next prompt.
+    fn show_error(message: String) {
+        let notification = Notification {
+            message,
+            notification_type: NotificationType::Error,
+        };
+        println!("{}", notification);
+    }
+}
+
+impl PlayerController for StdinController {
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates> {
+        loop {
+            match Coordinates::from_user_input(&Self::prompt(game, &[])?) {
+                Ok(coords) if game.legal_moves().contains(&coords) => return Ok(coords),
+                Ok(coords) => {
+                    Self::show_error(format!("{} isn't an empty tile on the board.", coords))
+                }
+                Err(error) => Self::show_error(error.to_string()),
+            }
+        }
+    }
+
+    fn take_turn(&mut self, game: &Game) -> Result<Turn> {
+        let mut commands = vec!["save"];
+        if game.can_undo() {
+            commands.push("undo");
+        }
+        if game.can_redo() {
+            commands.push("redo");
+        }
+        loop {
+            let input = Self::prompt(game, &commands)?;
+            // commands are a single word, except for `save`, which can be followed by a file path
+            let input = input.trim();
+            let (command, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+            let argument = argument.trim();
+            match command.to_lowercase().as_str() {
+                "save" => return Ok(Turn::Save((!argument.is_empty()).then(|| argument.into()))),
+                "undo" if argument.is_empty() => return Ok(Turn::Undo),
+                "redo" if argument.is_empty() => return Ok(Turn::Redo),
+                _ => {}
+            }
+            // whether the move is legal is for the frontend to check, so it can point out the
+            // occupied tile on the board
+            match Coordinates::from_user_input(input) {
+                Ok(coords) => return Ok(Turn::Move(coords)),
+                Err(error) => Self::show_error(error.to_string()),
+            }
+        }
+    }
+
+    fn is_person(&self) -> bool {
+        true
+    }
+}
+
+impl PlayerController for RandomController {
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates> {
+        game.legal_moves()
+            .choose(&mut rand::rng())
+            .copied()
+            .context("Found no move to make.")
+    }
+}
+
+impl PlayerController for EngineController {
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates> {
+        ai::choose_move(game, self.0).context("Computer found no move to make.")
+    }
+}
+
+impl ScriptedController {
+    /// Plays the given moves, first to last.
+    pub fn new(moves: impl IntoIterator<Item = Coordinates>) -> Self {
+        Self {
+            moves: moves.into_iter().collect(),
+        }
+    }
+
+    /// Parses moves written the way players type them, separated by whitespace, e.g. "B2 A1".
+    pub fn from_user_input(input: &str) -> Result<Self> {
+        let moves = input
+            .split_whitespace()
+            .map(Coordinates::from_user_input)
+            .collect::<Result<Vec<Coordinates>>>()?;
+        Ok(Self::new(moves))
+    }
+}
+
+impl PlayerController for ScriptedController {
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates> {
+        self.moves.pop_front().context(format!(
+            "{} has no scripted moves left.",
+            game.get_current_turn_player()
+        ))
+    }
+}
+
+/// The usual controller for a player of the given kind: `StdinController` for people, and an
+/// `EngineController` for the computer.
+pub fn controller_for(kind: PlayerKind) -> Box<dyn PlayerController> {
+    match kind {
+        PlayerKind::Human => Box::new(StdinController),
+        PlayerKind::Computer(engine) => Box::new(EngineController(engine)),
+    }
+}
+
+/// Plays the game through to the end, asking the controller at the same index as the current
+/// turn's player (in `Game::players`) for each move. Fails if there isn't exactly one
+/// controller per player, if a controller fails, or if one chooses an illegal move.
+pub fn play_out(game: &mut Game, controllers: &mut [Box<dyn PlayerController>]) -> Result<()> {
+    if controllers.len() != game.players.len() {
+        return Err(anyhow!(
+            "Got {} controllers for a game with {} players.",
+            controllers.len(),
+            game.players.len()
+        ));
+    }
+    while game.outcome() == GameOutcome::InProgress {
+        let current_player = game.get_current_turn_player();
+        let controller = &mut controllers[usize::from(current_player.number - 1)];
+        let coords = controller.choose_move(game)?;
+        game.play_move(coords).context(format!(
+            "{}'s controller chose an illegal move.",
+            current_player
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::GameSettings;
+
+    fn scripted(moves: &str) -> Box<dyn PlayerController> {
+        Box::new(ScriptedController::from_user_input(moves).unwrap())
+    }
+
+    #[test]
+    fn plays_a_scripted_game_through_to_the_end() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        play_out(&mut game, &mut [scripted("a1 a2 A3"), scripted("B1 b2")]).unwrap();
+        assert!(game.outcome() == GameOutcome::Victory(game.players[0].clone()));
+        assert_eq!(
+            game.moves(),
+            ["A1", "B1", "A2", "B2", "A3"]
+                .map(|input| Coordinates::from_user_input(input).unwrap())
+        );
+    }
+
+    #[test]
+    fn random_play_never_beats_a_perfect_engine() {
+        for _ in 0..10 {
+            let mut game = Game::new(GameSettings::default()).unwrap();
+            play_out(
+                &mut game,
+                &mut [
+                    Box::new(RandomController),
+                    Box::new(EngineController(Engine::Minimax(Difficulty::Perfect))),
+                ],
+            )
+            .unwrap();
+            assert!(game.outcome() != GameOutcome::InProgress);
+            assert!(game.outcome() != GameOutcome::Victory(game.players[0].clone()));
+        }
+    }
+
+    #[test]
+    fn needs_one_controller_per_player() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        assert!(play_out(&mut game, &mut [scripted("B2")]).is_err());
+        assert!(play_out(
+            &mut game,
+            &mut [scripted("B2"), scripted("A1"), scripted("C3")]
+        )
+        .is_err());
+        assert!(game.moves().is_empty());
+    }
+
+    #[test]
+    fn fails_on_illegal_moves_and_running_out_of_moves() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        let error = play_out(&mut game, &mut [scripted("B2 C3"), scripted("B2")]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Player 2's controller chose an illegal move."
+        );
+        assert_eq!(game.moves().len(), 1);
+
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        let error = play_out(&mut game, &mut [scripted("B2 C3"), scripted("A1")]).unwrap_err();
+        assert_eq!(error.to_string(), "Player 2 has no scripted moves left.");
+        assert_eq!(game.moves().len(), 3);
+    }
+
+    #[test]
+    fn only_people_take_turns_other_than_moves() {
+        let game = Game::new(GameSettings::default()).unwrap();
+        let mut controller = ScriptedController::from_user_input("B2").unwrap();
+        assert_eq!(
+            controller.take_turn(&game).unwrap(),
+            Turn::Move(Coordinates::from_user_input("B2").unwrap())
+        );
+        assert!(!controller.is_person());
+        assert!(StdinController.is_person());
+        assert!(ScriptedController::from_user_input("B2 middle").is_err());
+    }
+}
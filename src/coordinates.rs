@@ -0,0 +1,134 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::Game;
+
+// zero-indexed!
+pub(crate) struct Indices {
+    pub(crate) column: usize,
+    pub(crate) row: usize,
+}
+
+/// A tile's position as players see it: a column letter followed by a one-indexed row
+/// number, displayed as e.g. "A1".
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Coordinates {
+    column: char,
+    row: usize, // one-indexed!
+}
+
+impl Coordinates {
+    pub(crate) const COLUMN_LETTERS: [char; Game::MAX_NUM_ROWS_OR_COLUMNS] =
+        ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'];
+
+    /// The coordinates of the tile in the given zero-indexed row and column; e.g. row 0, column 1
+    /// is B1. Fails if the column is past the last letter any board can have.
+    pub fn from_row_and_column(row_index: usize, column_index: usize) -> Result<Coordinates> {
+        Self::from_indices(&Indices {
+            row: row_index,
+            column: column_index,
+        })
+    }
+
+    /// The zero-indexed (row, column) of the tile at these coordinates, the reverse of
+    /// `from_row_and_column`, or None for coordinates like A0 that no board has.
+    pub fn row_and_column(self) -> Option<(usize, usize)> {
+        let indices = self.to_indices().ok()?;
+        Some((indices.row, indices.column))
+    }
+
+    pub(crate) fn from_indices(indices: &Indices) -> Result<Coordinates> {
+        let row = indices.row + 1;
+        let &column = Self::COLUMN_LETTERS.get(indices.column).context(format!(
+            "No letter found to match zero-indexed column number {}; there are {} letter(s) total.",
+            indices.column,
+            Self::COLUMN_LETTERS.len()
+        ))?;
+        Ok(Coordinates { row, column })
+    }
+
+    pub(crate) fn to_indices(self) -> Result<Indices> {
+        let row = self.row.checked_sub(1).context(format!(
+            "Row numbers start at 1, so coordinates {} have no matching row.",
+            self
+        ))?;
+        let column = Self::COLUMN_LETTERS
+            .iter()
+            .position(|&letter| letter == self.column)
+            .context(format!(
+                "No zero-indexed column number found to match letter {}.",
+                self.column
+            ))?;
+        Ok(Indices { row, column })
+    }
+
+    /// Parses coordinates as a player might type them, e.g. "b2" or " B-2 ". Doesn't check
+    /// whether they're actually on any particular game board.
+    pub fn from_user_input(input: &str) -> Result<Coordinates> {
+        // Match a single alphabetical character followed by a number with one or more digits;
+        // whitespace and arbitrary punctuation are allowed at the beginning, end, and in between
+        // the character and digits (just not within the digits).
+        let re =
+            Regex::new(r"^[\s|[[:punct:]]]*([[:alpha:]])[\s|[[:punct:]]]*(\d+)[\s|[[:punct:]]]*$")?;
+        let cap = re.captures(input).context(format!(
+            "Could not parse '{}' as coordinates. Valid example: A1",
+            input.trim()
+        ))?;
+        let column = char::from_str(&cap[1])?.to_ascii_uppercase();
+        let row = usize::from_str(&cap[2])?;
+        Ok(Self { column, row })
+    }
+}
+
+impl fmt::Display for Coordinates {
+    // print coords as e.g. "A1"
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.column, self.row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_coordinates_however_they_are_typed() {
+        for input in ["B2", "b2", " B-2 \n", "(b, 2)"] {
+            assert_eq!(
+                Coordinates::from_user_input(input).unwrap().to_string(),
+                "B2"
+            );
+        }
+        assert_eq!(
+            Coordinates::from_user_input("h10").unwrap().to_string(),
+            "H10"
+        );
+    }
+
+    #[test]
+    fn rejects_input_that_is_not_coordinates() {
+        for input in ["", "B", "2", "2B", "BB2", "B2 C3", "B 1 2"] {
+            assert!(Coordinates::from_user_input(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_indices() {
+        let coords = Coordinates::from_row_and_column(2, 1).unwrap();
+        assert_eq!(coords.to_string(), "B3");
+        assert_eq!(coords.row_and_column(), Some((2, 1)));
+        assert!(Coordinates::from_row_and_column(0, Game::MAX_NUM_ROWS_OR_COLUMNS).is_err());
+        assert_eq!(
+            Coordinates::from_user_input("A0").unwrap().row_and_column(),
+            None
+        );
+        assert_eq!(
+            Coordinates::from_user_input("Z1").unwrap().row_and_column(),
+            None
+        );
+    }
+}
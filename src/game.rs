@@ -0,0 +1,1121 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthChar;
+
+use crate::ai::{Difficulty, Engine};
+use crate::bitboard::{self, BitBoard};
+use crate::{Coordinates, Indices};
+
+pub(crate) struct Tile<'a> {
+    occupation_state: TileOccupationState<'a>,
+    display_state: TileDisplayState,
+}
+
+impl fmt::Display for Tile<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use TileDisplayState::*;
+        use TileOccupationState::*;
+        write!(
+            f,
+            "{}",
+            match self.occupation_state {
+                Empty => " ".normal(),
+                // highlights that mean something about the move itself override the player's
+                // color; the newest move just stands out in it
+                Occupied(player) => match self.display_state {
+                    NewlyCreated => player.colored_mark().bold(),
+                    Victory => String::from(player.mark).green().bold(),
+                    Error => String::from(player.mark).red().bold(),
+                    Normal => player.colored_mark(),
+                },
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) enum TileOccupationState<'a> {
+    Empty,
+    Occupied(&'a Player),
+}
+
+// lets us render the winning line of tiles in green
+#[derive(Clone, Copy)]
+pub(crate) enum TileDisplayState {
+    Error,
+    NewlyCreated,
+    Normal,
+    Victory,
+}
+
+// Which player occupies each tile lives in a bitboard, which is what win detection and the
+// computer players work with; how each tile should be displayed only matters for rendering,
+// so it's tracked separately, indexed the same way.
+pub(crate) struct Board {
+    pub(crate) bits: BitBoard,
+    display_states: Vec<TileDisplayState>,
+}
+
+/// One of the players in a `Game`.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Player {
+    /// One-indexed position in `Game::players`.
+    pub number: u8,
+    /// What the player is called in prompts and notifications, e.g. "Player 1" by default.
+    /// Games saved before players had names don't have one, which leaves it empty until
+    /// the game is loaded.
+    #[serde(default)]
+    pub name: String,
+    /// The character this player's tiles are rendered with.
+    pub mark: char,
+    /// The color this player's marks are drawn in. Games saved before players had colors
+    /// don't have one, which leaves it None until the game is loaded.
+    #[serde(default)]
+    pub color: Option<PlayerColor>,
+    /// Whether a person or the computer makes this player's moves.
+    pub kind: PlayerKind,
+}
+
+/// A color a player's marks can be drawn in. Red and green aren't among them, since they
+/// already mean an error and a winning line.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerColor {
+    Cyan,
+    Magenta,
+    Yellow,
+    Blue,
+    White,
+    BrightCyan,
+    BrightMagenta,
+    BrightYellow,
+    BrightBlue,
+    BrightWhite,
+    /// Shows up as gray in most terminals
+    BrightBlack,
+}
+
+impl PlayerColor {
+    // Each player's marks are drawn in a color of their own by default, so that it's easy to
+    // tell whose are whose with several players on a big board.
+    const DEFAULTS: [PlayerColor; Game::MAX_NUM_PLAYERS] = [
+        Self::Cyan,
+        Self::Magenta,
+        Self::Yellow,
+        Self::Blue,
+        Self::BrightCyan,
+        Self::BrightMagenta,
+        Self::BrightYellow,
+        Self::BrightBlue,
+    ];
+
+    /// The color the player with the given number gets if they aren't given one.
+    pub fn default_for(number: u8) -> Self {
+        Self::DEFAULTS[usize::from(number - 1) % Self::DEFAULTS.len()]
+    }
+}
+
+impl From<PlayerColor> for Color {
+    fn from(color: PlayerColor) -> Self {
+        match color {
+            PlayerColor::Cyan => Color::Cyan,
+            PlayerColor::Magenta => Color::Magenta,
+            PlayerColor::Yellow => Color::Yellow,
+            PlayerColor::Blue => Color::Blue,
+            PlayerColor::White => Color::White,
+            PlayerColor::BrightCyan => Color::BrightCyan,
+            PlayerColor::BrightMagenta => Color::BrightMagenta,
+            PlayerColor::BrightYellow => Color::BrightYellow,
+            PlayerColor::BrightBlue => Color::BrightBlue,
+            PlayerColor::BrightWhite => Color::BrightWhite,
+            PlayerColor::BrightBlack => Color::BrightBlack,
+        }
+    }
+}
+
+/// Whether a player's moves come from a person or from the `ai` module.
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerKind {
+    Human,
+    /// The computer, choosing its moves with the given engine.
+    Computer(Engine),
+}
+
+impl Player {
+    /// The player's mark, drawn in their color.
+    pub fn colored_mark(&self) -> ColoredString {
+        let color = self
+            .color
+            .unwrap_or_else(|| PlayerColor::default_for(self.number));
+        String::from(self.mark).color(color)
+    }
+
+    /// Fails if a player couldn't go by the given name: it can't be blank, and it can't
+    /// contain control characters, since names end up in line-based formats like game records
+    /// and network messages. `Game::new` checks every name it's given, but names from anywhere
+    /// else (like another player's process) need checking before they're given to a `Player`.
+    pub fn validate_name(name: &str) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("Player names can't be blank."));
+        }
+        if name.contains(char::is_control) {
+            return Err(anyhow!(
+                "Player names can't contain control characters like line breaks."
+            ));
+        }
+        Ok(())
+    }
+
+    /// The name a player goes by if they aren't given one, e.g. "Player 1".
+    pub fn default_name(number: u8) -> String {
+        format!("Player {}", number)
+    }
+
+    /// The name a computer player goes by if it isn't given one, after the engine it plays
+    /// with, e.g. "Computer (minimax, perfect)", so its results are told apart from people's
+    /// and from other engines'. When several computers play in one game, each name also has
+    /// the player's number, e.g. "Computer 3 (minimax, perfect)".
+    pub fn default_computer_name(engine: Engine, number: Option<u8>) -> String {
+        match number {
+            Some(number) => format!("Computer {} ({})", number, engine),
+            None => format!("Computer ({})", engine),
+        }
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// How a `Notification` should be presented.
+pub enum NotificationType {
+    /// Good news, like a win; shown in bold green.
+    Success,
+    /// Anything else worth knowing, like the computer's last move; shown as is.
+    Info,
+    /// Something that went wrong, like an illegal move; shown after a red "Error!".
+    Error,
+}
+
+/// A message for the players about what just happened, e.g. that someone won. Its `Display`
+/// impl colors it according to its type.
+pub struct Notification {
+    /// The text to show, as a full sentence.
+    pub message: String,
+    pub notification_type: NotificationType,
+}
+
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use NotificationType::*;
+        let message = &self.message;
+        write!(
+            f,
+            "{}",
+            match self.notification_type {
+                Success => message.green().bold(),
+                Info => message.normal(),
+                Error => format!("{} {}", "Error!".red().bold(), message).normal(),
+            }
+        )
+    }
+}
+
+/// Whether a game is over, and if so, how it ended.
+#[derive(PartialEq, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameOutcome {
+    InProgress,
+    /// Every tile is taken, with nobody having won.
+    Draw,
+    /// The given player completed a line.
+    Victory(Player),
+}
+
+/// Why `Game::play_move` rejected a move. Moves fail with an `anyhow::Error` like everything
+/// else, but one wrapping a `MoveError`, so callers that care which problem it was can find
+/// out with `downcast_ref`. Each variant holds the coordinates of the rejected move.
+#[derive(PartialEq, Clone, Debug)]
+pub enum MoveError {
+    /// The game already ended in a win or a draw.
+    GameOver { coords: Coordinates },
+    /// The coordinates are past the board's last row or column.
+    OffBoard { coords: Coordinates },
+    /// The given player already has a mark on the tile.
+    Occupied { coords: Coordinates, player: Player },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GameOver { coords } => {
+                write!(f, "Cannot play {}; the game is already over.", coords)
+            }
+            Self::OffBoard { coords } => {
+                write!(f, "Could not find coordinates {} on game board.", coords)
+            }
+            Self::Occupied { coords, player } => {
+                write!(f, "Tile {} is already occupied by {}.", coords, player)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Everything needed to set up a new `Game`. The default is a classic game: a 3x3 board,
+/// three in a row to win, and two people playing X and O, with X moving first.
+pub struct GameSettings {
+    /// The board's height, from `Game::MIN_NUM_ROWS_OR_COLUMNS` to
+    /// `Game::MAX_NUM_ROWS_OR_COLUMNS`.
+    pub num_rows: usize,
+    /// The board's width, within the same bounds as its height.
+    pub num_columns: usize,
+    /// How many marks in a row it takes to win; None means as many as fit along the board's
+    /// shorter side.
+    pub win_length: Option<usize>,
+    /// One mark per player, in turn order; there can be anywhere from
+    /// `Game::MIN_NUM_PLAYERS` to `Game::MAX_NUM_PLAYERS` of them. Any character will do, as
+    /// long as it's visible and takes up a single column in a terminal.
+    pub marks: Vec<char>,
+    /// Players' names, in turn order. Players without one (including every player past the
+    /// end of the list) go by `Player::default_name`, or `Player::default_computer_name` for
+    /// computer players.
+    pub names: Vec<String>,
+    /// Players' colors, in turn order. Players without one get `PlayerColor::default_for`
+    /// their number. Players may share a color, since their marks still tell them apart.
+    pub colors: Vec<PlayerColor>,
+    /// The number of the player who moves first, counting from 1.
+    pub first_player_number: u8,
+    /// Which players the computer moves for, rather than a person.
+    pub computer_player_numbers: Vec<u8>,
+    /// The engine every computer player chooses its moves with.
+    pub computer_engine: Engine,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            num_rows: 3,
+            num_columns: 3,
+            win_length: None,
+            marks: vec!['X', 'O'],
+            names: vec![],
+            colors: vec![],
+            first_player_number: 1,
+            computer_player_numbers: vec![],
+            computer_engine: Engine::Minimax(Difficulty::Perfect),
+        }
+    }
+}
+
+// a mark placed on the board, as recorded in a game's history
+#[derive(Clone, Copy)]
+struct Move {
+    tile_index: usize,
+    player_index: usize,
+}
+
+/// A single game: its players, its board, and whose turn it is.
+pub struct Game {
+    /// Everyone playing, in turn order, starting from player 1 (whoever moves first).
+    pub players: Vec<Player>,
+    pub(crate) board: Board,
+    notification: Option<Notification>,
+    // every move so far, oldest first
+    moves: Vec<Move>,
+    // moves taken back by `undo`, most recently undone last, until a new move is made
+    undone_moves: Vec<Move>,
+    win_length: usize,
+    first_player_index: usize,
+    turn_number: usize,
+    outcome: GameOutcome,
+}
+
+impl Game {
+    /// The fewest rows or columns a board can have.
+    pub const MIN_NUM_ROWS_OR_COLUMNS: usize = 1;
+    /// The most rows or columns a board can have, so that every board fits in a bitboard.
+    pub const MAX_NUM_ROWS_OR_COLUMNS: usize = 8;
+    /// The fewest players a game can have.
+    pub const MIN_NUM_PLAYERS: usize = 2;
+    /// The most players a game can have.
+    pub const MAX_NUM_PLAYERS: usize = 8;
+
+    /// Places the current turn's player's mark at the given coordinates, then checks whether
+    /// that ended the game. Fails, leaving the game as it was, if the game is already over or
+    /// the coordinates are off the board or already occupied.
+    pub fn play_move(&mut self, coords: Coordinates) -> Result<()> {
+        if self.outcome != GameOutcome::InProgress {
+            return Err(MoveError::GameOver { coords }.into());
+        }
+        self.update_board(coords, self.current_player_index())?;
+        self.update_outcome();
+        // a new move starts a new line of play, so there's nothing left to redo
+        self.undone_moves.clear();
+        Ok(())
+    }
+
+    /// Takes back the latest move, reopening the game if that move ended it. Returns the
+    /// coordinates of the undone move, which `redo` can replay until a new move is made.
+    pub fn undo(&mut self) -> Result<Coordinates> {
+        let last_move = self.moves.pop().context("There are no moves to undo.")?;
+        self.board
+            .bits
+            .remove(last_move.tile_index, last_move.player_index);
+        // a move that ended the game didn't advance the turn, so there's no turn to go back on
+        if self.outcome == GameOutcome::InProgress {
+            self.turn_number -= 1;
+        } else {
+            self.outcome = GameOutcome::InProgress;
+        }
+        self.notification = None;
+        // highlight the move before the undone one, as if it had just been made
+        self.board.display_states.fill(TileDisplayState::Normal);
+        if let Some(previous_move) = self.moves.last() {
+            self.board.display_states[previous_move.tile_index] = TileDisplayState::NewlyCreated;
+        }
+        self.undone_moves.push(last_move);
+        Ok(self.coordinates(last_move.tile_index))
+    }
+
+    /// Replays the most recently undone move, returning its coordinates.
+    pub fn redo(&mut self) -> Result<Coordinates> {
+        let next_move = self
+            .undone_moves
+            .pop()
+            .context("There are no moves to redo.")?;
+        let coords = self.coordinates(next_move.tile_index);
+        self.update_board(coords, next_move.player_index)?;
+        self.update_outcome();
+        Ok(coords)
+    }
+
+    /// Whether there's a move for `undo` to take back.
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    /// Whether there's an undone move for `redo` to replay.
+    pub fn can_redo(&self) -> bool {
+        !self.undone_moves.is_empty()
+    }
+
+    /// Whether the game is still going, and if not, who won (if anyone).
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome.clone()
+    }
+
+    /// Every move the current turn's player could make, i.e. every empty tile, or none at all
+    /// once the game is over.
+    pub fn legal_moves(&self) -> Vec<Coordinates> {
+        if self.outcome != GameOutcome::InProgress {
+            return vec![];
+        }
+        self.board
+            .bits
+            .empty_tiles()
+            .map(|tile_index| self.coordinates(tile_index))
+            .collect()
+    }
+
+    /// One-indexed; advances after every move that doesn't end the game.
+    pub fn turn_number(&self) -> usize {
+        self.turn_number
+    }
+
+    /// Every move made so far, oldest first, not counting any that were undone.
+    pub fn moves(&self) -> Vec<Coordinates> {
+        self.moves
+            .iter()
+            .map(|each_move| self.coordinates(each_move.tile_index))
+            .collect()
+    }
+
+    /// The player occupying each tile, if any, one row at a time from the top of the board,
+    /// left to right within each row.
+    pub fn tiles(&self) -> Vec<Vec<Option<Player>>> {
+        (0..self.num_rows())
+            .map(|row| {
+                (0..self.num_columns())
+                    .map(|column| {
+                        let tile_index = self.board.bits.tile_index(&Indices { row, column })?;
+                        let player_index = self.board.bits.occupier(tile_index)?;
+                        Some(self.players[player_index].clone())
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The board's height.
+    pub fn num_rows(&self) -> usize {
+        self.board.bits.num_tiles() / self.board.bits.num_columns()
+    }
+
+    /// The board's width.
+    pub fn num_columns(&self) -> usize {
+        self.board.bits.num_columns()
+    }
+
+    /// How many marks in a row it takes to win.
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// The number of the player who made (or will make) the game's first move.
+    pub fn first_player_number(&self) -> u8 {
+        self.players[self.first_player_index].number
+    }
+
+    /// Removes and returns the latest notification, if there is one that hasn't been shown yet.
+    pub fn take_notification(&mut self) -> Option<Notification> {
+        self.notification.take()
+    }
+
+    /// The latest notification, if there is one, left in place for `take_notification`.
+    pub fn notification(&self) -> Option<&Notification> {
+        self.notification.as_ref()
+    }
+
+    /// Replaces the latest notification, e.g. to tell players what a frontend just did.
+    pub fn set_notification(&mut self, notification: Notification) {
+        self.notification = Some(notification);
+    }
+
+    /// Renders the mark at the given coordinates (if they're on the board) in red, until the
+    /// next move.
+    pub fn highlight_error(&mut self, coords: Coordinates) {
+        if let Some(tile_index) = self.tile_index(coords) {
+            self.board.display_states[tile_index] = TileDisplayState::Error;
+        }
+    }
+
+    // places the mark of the player at `player_index` in `players`
+    fn update_board(&mut self, coords_for_move: Coordinates, player_index: usize) -> Result<()> {
+        // validate against both the "coordinates don't even exist on game board"
+        // and the "coordinates refer to an already-occupied tile" edge cases
+        let tile_index = self
+            .tile_index(coords_for_move)
+            .ok_or(MoveError::OffBoard {
+                coords: coords_for_move,
+            })?;
+        if let TileOccupationState::Occupied(player) = self.get_tile(tile_index).occupation_state {
+            return Err(MoveError::Occupied {
+                coords: coords_for_move,
+                player: player.clone(),
+            }
+            .into());
+        }
+
+        // clear any previous display states from tiles, then place the new mark
+        self.board.display_states.fill(TileDisplayState::Normal);
+        self.board.bits.place(tile_index, player_index);
+        self.board.display_states[tile_index] = TileDisplayState::NewlyCreated;
+        self.moves.push(Move {
+            tile_index,
+            player_index,
+        });
+        Ok(())
+    }
+
+    // checks for all possible victory states -- either player occupies every tile in
+    // any one of the board's winning lines (see `BitBoard::build_win_masks`); also checks
+    // for a draw (all tiles are occupied, but there is no victor)
+    fn update_outcome(&mut self) {
+        if let Some((player_index, line_mask)) = self.board.bits.find_completed_line() {
+            let player = &self.players[player_index];
+            self.notification = Some(Notification {
+                message: format!("{} wins!", player),
+                notification_type: NotificationType::Success,
+            });
+            self.outcome = GameOutcome::Victory(player.clone());
+            // update the tiles from the winning line to render as winners
+            for tile_index in bitboard::tile_indices(line_mask) {
+                self.board.display_states[tile_index] = TileDisplayState::Victory;
+            }
+            return;
+        }
+
+        // we will only reach this point if no one has won yet; if every tile is in fact
+        // occupied, the game must be a draw
+        if self.board.bits.is_full() {
+            self.outcome = GameOutcome::Draw;
+            self.notification = Some(Notification {
+                message: "The game ends in a draw!".to_string(),
+                notification_type: NotificationType::Info,
+            });
+            return;
+        }
+
+        // we will only reach this point if the game is still InProgress
+        self.advance_turn();
+    }
+
+    // None if the coordinates are off the game board
+    fn tile_index(&self, coords: Coordinates) -> Option<usize> {
+        let indices = coords.to_indices().ok()?;
+        self.board.bits.tile_index(&indices)
+    }
+
+    fn coordinates(&self, tile_index: usize) -> Coordinates {
+        Coordinates::from_indices(&self.board.bits.indices(tile_index)).unwrap()
+    }
+
+    fn get_tile(&self, tile_index: usize) -> Tile<'_> {
+        Tile {
+            occupation_state: match self.board.bits.occupier(tile_index) {
+                Some(player_index) => TileOccupationState::Occupied(&self.players[player_index]),
+                None => TileOccupationState::Empty,
+            },
+            display_state: self.board.display_states[tile_index],
+        }
+    }
+
+    fn advance_turn(&mut self) {
+        self.turn_number += 1;
+    }
+
+    /// The player whose turn it is; once the game is over, whoever made the last move.
+    pub fn get_current_turn_player(&self) -> Player {
+        self.players[self.current_player_index()].clone()
+    }
+
+    fn current_player_index(&self) -> usize {
+        (self.first_player_index + self.turn_number - 1) % self.players.len()
+    }
+
+    /// Renders the board as a grid of marks with column letters across the top and row
+    /// numbers down the side, colored to highlight the latest move or the winning line.
+    pub fn render_board(&self) -> String {
+        self.render_board_with_cursor(None)
+    }
+
+    /// Renders the board like `render_board`, with brackets around the tile at the cursor's
+    /// coordinates (if they're on the board).
+    pub fn render_board_with_cursor(&self, cursor: Option<Coordinates>) -> String {
+        let cursor_tile_index = cursor.and_then(|coords| self.tile_index(coords));
+        let mut rendered_grid = String::new();
+        let num_columns = self.board.bits.num_columns();
+        let num_rows = self.board.bits.num_tiles() / num_columns;
+        let column_headers = Coordinates::COLUMN_LETTERS
+            .iter()
+            .take(num_columns)
+            .map(|char| format!(" {} ", char))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let column_header_row = format!("   {}\n", column_headers.dimmed());
+        rendered_grid.push_str(&column_header_row);
+        rendered_grid.push('\n');
+        for row_index in 0..num_rows {
+            let mut cells: Vec<String> = Vec::new();
+            for column_index in 0..num_columns {
+                let tile_index = self
+                    .board
+                    .bits
+                    .tile_index(&Indices {
+                        row: row_index,
+                        column: column_index,
+                    })
+                    .unwrap();
+                let tile = self.get_tile(tile_index);
+                cells.push(if Some(tile_index) == cursor_tile_index {
+                    format!("[{}]", tile)
+                } else {
+                    format!(" {} ", tile)
+                });
+            }
+            let row_number = (row_index + 1).to_string();
+            let tiles = cells.join("|");
+            let tiles_row = format!("{}  {}\n", row_number.dimmed(), tiles);
+            rendered_grid.push_str(&tiles_row);
+            // If the row we just added wasn't the last one...
+            if row_index + 1 < num_rows {
+                // ... then build and add a divider row.
+                let divider = vec!["---"; num_columns].join("+");
+                let divider_row = format!("   {}\n", divider);
+                rendered_grid.push_str(&divider_row);
+            }
+        }
+        rendered_grid
+    }
+
+    /// The coordinates of the tile drawn at the given zero-indexed line and column of
+    /// `render_board`'s output, if there is one there -- e.g. to find which tile was clicked.
+    /// Column headers, row numbers and the dividers between tiles don't count as any tile.
+    pub fn coordinates_at(&self, line: usize, column: usize) -> Option<Coordinates> {
+        // the column header row and a blank line come first, then every row of tiles but the
+        // last is followed by a divider row
+        let row_index = line.checked_sub(2).filter(|line| line % 2 == 0)? / 2;
+        // every row starts with its number and two spaces, followed by tiles three columns wide
+        // with a "|" between each pair of them
+        let column_index = column.checked_sub(3).filter(|column| column % 4 != 3)? / 4;
+        let tile_index = self.board.bits.tile_index(&Indices {
+            row: row_index,
+            column: column_index,
+        })?;
+        Some(self.coordinates(tile_index))
+    }
+
+    /// A fresh game on the same board with the same players, started by whoever moves after
+    /// the player who started this one. Playing a series of games by rematching takes everyone
+    /// in turn through moving first, which is a big advantage on small boards.
+    pub fn rematch(&self) -> Self {
+        let num_tiles = self.board.bits.num_tiles();
+        Self {
+            players: self.players.clone(),
+            board: Board {
+                bits: BitBoard::new(
+                    self.num_rows(),
+                    self.num_columns(),
+                    self.win_length,
+                    self.players.len(),
+                ),
+                display_states: vec![TileDisplayState::Normal; num_tiles],
+            },
+            notification: None,
+            moves: vec![],
+            undone_moves: vec![],
+            win_length: self.win_length,
+            first_player_index: (self.first_player_index + 1) % self.players.len(),
+            turn_number: 1,
+            outcome: GameOutcome::InProgress,
+        }
+    }
+
+    /// Sets up a new game, failing if the settings don't describe a playable one.
+    pub fn new(settings: GameSettings) -> Result<Self> {
+        let (num_rows, num_columns) = (settings.num_rows, settings.num_columns);
+        for num_rows_or_columns in [num_rows, num_columns] {
+            if !(Self::MIN_NUM_ROWS_OR_COLUMNS..=Self::MAX_NUM_ROWS_OR_COLUMNS)
+                .contains(&num_rows_or_columns)
+            {
+                return Err(anyhow!(
+                    "Number of rows/columns on game board must be between {} and {}.",
+                    Self::MIN_NUM_ROWS_OR_COLUMNS,
+                    Self::MAX_NUM_ROWS_OR_COLUMNS
+                ));
+            }
+        }
+
+        // a winning line has to fit along at least one side of the board
+        let max_win_length = num_rows.max(num_columns);
+        let win_length = settings.win_length.unwrap_or(num_rows.min(num_columns));
+        if !(1..=max_win_length).contains(&win_length) {
+            return Err(anyhow!(
+                "Win length must be between 1 and {} on a board with {} row(s) and {} column(s), but got {}.",
+                max_win_length,
+                num_rows,
+                num_columns,
+                win_length
+            ));
+        }
+
+        if !(Self::MIN_NUM_PLAYERS..=Self::MAX_NUM_PLAYERS).contains(&settings.marks.len()) {
+            return Err(anyhow!(
+                "Expected between {} and {} player marks (one per player), but got {}.",
+                Self::MIN_NUM_PLAYERS,
+                Self::MAX_NUM_PLAYERS,
+                settings.marks.len()
+            ));
+        }
+        for (mark_index, &mark) in settings.marks.iter().enumerate() {
+            // anything wider or narrower than a single column would throw off the board's grid
+            if mark.is_whitespace() || mark.width() != Some(1) {
+                return Err(anyhow!(
+                    "'{}' can't be used as a mark; marks must be a single visible character, one column wide.",
+                    mark
+                ));
+            }
+            if settings.marks[..mark_index].contains(&mark) {
+                return Err(anyhow!(
+                    "Players must use different marks, but more than one chose '{}'.",
+                    mark
+                ));
+            }
+        }
+        if settings.names.len() > settings.marks.len() {
+            return Err(anyhow!(
+                "Got {} player names, but only {} players.",
+                settings.names.len(),
+                settings.marks.len()
+            ));
+        }
+        for name in &settings.names {
+            Player::validate_name(name)?;
+        }
+        if settings.colors.len() > settings.marks.len() {
+            return Err(anyhow!(
+                "Got {} player colors, but only {} players.",
+                settings.colors.len(),
+                settings.marks.len()
+            ));
+        }
+        let num_computer_players = (1..=settings.marks.len() as u8)
+            .filter(|number| settings.computer_player_numbers.contains(number))
+            .count();
+        let players = settings
+            .marks
+            .iter()
+            .zip(1..)
+            .map(|(&mark, number)| {
+                let is_computer = settings.computer_player_numbers.contains(&number);
+                Player {
+                    number,
+                    name: settings
+                        .names
+                        .get(usize::from(number - 1))
+                        .cloned()
+                        .unwrap_or_else(|| {
+                            if is_computer {
+                                Player::default_computer_name(
+                                    settings.computer_engine,
+                                    (num_computer_players > 1).then_some(number),
+                                )
+                            } else {
+                                Player::default_name(number)
+                            }
+                        }),
+                    mark,
+                    color: Some(
+                        settings
+                            .colors
+                            .get(usize::from(number - 1))
+                            .copied()
+                            .unwrap_or_else(|| PlayerColor::default_for(number)),
+                    ),
+                    kind: if is_computer {
+                        PlayerKind::Computer(settings.computer_engine)
+                    } else {
+                        PlayerKind::Human
+                    },
+                }
+            })
+            .collect::<Vec<Player>>();
+        // checked once every player has a name, since one given name could match another
+        // player's default name
+        for (player_index, player) in players.iter().enumerate() {
+            if players[..player_index]
+                .iter()
+                .any(|other| other.name == player.name)
+            {
+                return Err(anyhow!(
+                    "Players must have different names, but more than one is called '{}'.",
+                    player.name
+                ));
+            }
+        }
+
+        let first_player_index = players
+            .iter()
+            .position(|player| player.number == settings.first_player_number)
+            .context(format!(
+                "Cannot start with Player {}; there are only {} players.",
+                settings.first_player_number,
+                players.len()
+            ))?;
+
+        let board = Board {
+            bits: BitBoard::new(num_rows, num_columns, win_length, players.len()),
+            display_states: vec![TileDisplayState::Normal; num_rows * num_columns],
+        };
+        Ok(Self {
+            players,
+            board,
+            notification: None,
+            moves: vec![],
+            undone_moves: vec![],
+            win_length,
+            first_player_index,
+            turn_number: 1,
+            outcome: GameOutcome::InProgress,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &str) {
+        for input in moves.split_whitespace() {
+            game.play_move(Coordinates::from_user_input(input).unwrap())
+                .unwrap();
+        }
+    }
+
+    fn move_error(result: Result<()>) -> MoveError {
+        result.unwrap_err().downcast::<MoveError>().unwrap()
+    }
+
+    #[test]
+    fn first_to_complete_a_line_wins() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        play(&mut game, "A1 B1 A2 B2");
+        assert!(game.outcome() == GameOutcome::InProgress);
+        assert_eq!(game.get_current_turn_player().number, 1);
+        play(&mut game, "A3");
+        match game.outcome() {
+            GameOutcome::Victory(player) => assert_eq!(player.number, 1),
+            _ => panic!("expected player 1 to win"),
+        }
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn full_board_without_a_line_is_a_draw() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        play(&mut game, "B2 A1 C1 A3 A2 C2 B1 B3 C3");
+        assert!(game.outcome() == GameOutcome::Draw);
+    }
+
+    #[test]
+    fn names_default_to_player_numbers() {
+        let game = Game::new(GameSettings {
+            names: vec!["Ada".to_string()],
+            ..GameSettings::default()
+        })
+        .unwrap();
+        assert_eq!(game.players[0].name, "Ada");
+        assert_eq!(game.players[1].name, "Player 2");
+    }
+
+    #[test]
+    fn computer_players_are_named_after_their_engine() {
+        let game = Game::new(GameSettings {
+            computer_player_numbers: vec![2],
+            ..GameSettings::default()
+        })
+        .unwrap();
+        assert_eq!(game.players[1].name, "Computer (minimax, perfect)");
+        let game = Game::new(GameSettings {
+            marks: vec!['X', 'O', 'V'],
+            computer_player_numbers: vec![2, 3],
+            computer_engine: Engine::Minimax(Difficulty::Easy),
+            ..GameSettings::default()
+        })
+        .unwrap();
+        assert_eq!(game.players[2].name, "Computer 3 (minimax, easy)");
+    }
+
+    #[test]
+    fn rejects_names_shared_with_another_player() {
+        for names in [vec!["Ada", "Ada"], vec!["Player 2"]] {
+            let result = Game::new(GameSettings {
+                names: names.into_iter().map(String::from).collect(),
+                ..GameSettings::default()
+            });
+            assert!(result.is_err());
+        }
+    }
+
+    // the tiles rendered as part of the winning line, as user-typed coordinates
+    fn victory_tiles(game: &Game) -> Vec<String> {
+        (0..game.board.bits.num_tiles())
+            .filter(|&tile_index| {
+                matches!(
+                    game.board.display_states[tile_index],
+                    TileDisplayState::Victory
+                )
+            })
+            .map(|tile_index| game.coordinates(tile_index).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn wins_take_win_length_in_a_row_anywhere_on_the_board() {
+        let mut game = Game::new(GameSettings {
+            num_rows: 5,
+            num_columns: 5,
+            win_length: Some(4),
+            ..GameSettings::default()
+        })
+        .unwrap();
+        // three in a row isn't enough for O along row 5
+        play(&mut game, "B1 A5 C2 B5 D3 C5");
+        assert!(game.outcome() == GameOutcome::InProgress);
+        // X's diagonal is one off the main one, and stops short of the board's edge
+        play(&mut game, "E4");
+        match game.outcome() {
+            GameOutcome::Victory(player) => assert_eq!(player.number, 1),
+            _ => panic!("expected player 1 to win along the diagonal"),
+        }
+        assert_eq!(victory_tiles(&game), vec!["B1", "C2", "D3", "E4"]);
+    }
+
+    #[test]
+    fn rectangular_boards_have_their_own_width_and_height() {
+        let mut game = Game::new(GameSettings {
+            num_rows: 6,
+            num_columns: 7,
+            win_length: Some(4),
+            ..GameSettings::default()
+        })
+        .unwrap();
+        let rendered_board = game.render_board();
+        // the column letters and a blank line, then six rows of tiles with dividers between them
+        assert_eq!(rendered_board.lines().count(), 13);
+        assert!(rendered_board
+            .lines()
+            .next()
+            .unwrap()
+            .trim_end()
+            .ends_with('G'));
+        for off_board in ["H1", "A7"] {
+            let coords = Coordinates::from_user_input(off_board).unwrap();
+            assert!(game.play_move(coords).is_err(), "{}", off_board);
+        }
+        play(&mut game, "G3 A1 G4 A2 G5 A3 G6");
+        match game.outcome() {
+            GameOutcome::Victory(player) => assert_eq!(player.number, 1),
+            _ => panic!("expected player 1 to win down column G"),
+        }
+        assert_eq!(victory_tiles(&game), vec!["G3", "G4", "G5", "G6"]);
+    }
+
+    #[test]
+    fn turns_rotate_through_every_player() {
+        let mut game = Game::new(GameSettings {
+            num_rows: 4,
+            num_columns: 4,
+            win_length: Some(3),
+            marks: vec!['X', 'O', 'V'],
+            ..GameSettings::default()
+        })
+        .unwrap();
+        let mut turn_order = vec![];
+        for input in "A1 B1 C2 D4 B4 C3 A4 D2".split_whitespace() {
+            turn_order.push(game.get_current_turn_player().mark);
+            play(&mut game, input);
+        }
+        assert_eq!(turn_order, vec!['X', 'O', 'V', 'X', 'O', 'V', 'X', 'O']);
+        play(&mut game, "C4");
+        match game.outcome() {
+            GameOutcome::Victory(player) => {
+                assert_eq!(player.number, 3);
+                assert_eq!(player.mark, 'V');
+            }
+            _ => panic!("expected player 3 to win down column C"),
+        }
+        assert_eq!(victory_tiles(&game), vec!["C2", "C3", "C4"]);
+    }
+
+    #[test]
+    fn rejects_too_few_or_too_many_players() {
+        for num_players in [1, Game::MAX_NUM_PLAYERS + 1] {
+            let result = Game::new(GameSettings {
+                marks: "XOVABCDEFG".chars().take(num_players).collect(),
+                ..GameSettings::default()
+            });
+            assert!(result.is_err(), "{} players", num_players);
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_moves() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        play(&mut game, "A1 B1 A2 B2 A3");
+        assert!(game.outcome() != GameOutcome::InProgress);
+        assert_eq!(game.turn_number(), 5);
+
+        // undoing the winning move reopens the game, and clears its highlighting
+        assert_eq!(game.undo().unwrap().to_string(), "A3");
+        assert!(game.outcome() == GameOutcome::InProgress);
+        assert_eq!(game.turn_number(), 5);
+        assert_eq!(game.get_current_turn_player().number, 1);
+        assert!(victory_tiles(&game).is_empty());
+        let b2_index = game.tile_index(Coordinates::from_user_input("B2").unwrap());
+        assert!(matches!(
+            game.board.display_states[b2_index.unwrap()],
+            TileDisplayState::NewlyCreated
+        ));
+
+        assert_eq!(game.undo().unwrap().to_string(), "B2");
+        assert_eq!(game.turn_number(), 4);
+        assert_eq!(game.redo().unwrap().to_string(), "B2");
+        assert_eq!(game.turn_number(), 5);
+        // a new move replaces whatever was undone
+        play(&mut game, "C3");
+        assert!(!game.can_redo());
+        assert!(game.redo().is_err());
+        assert_eq!(game.moves().len(), 5);
+
+        while game.can_undo() {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.turn_number(), 1);
+        assert!(game.undo().is_err());
+    }
+
+    #[test]
+    fn rejects_illegal_moves_without_changing_the_game() {
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        play(&mut game, "B2");
+        let b2 = Coordinates::from_user_input("B2").unwrap();
+        assert!(matches!(
+            move_error(game.play_move(b2)),
+            MoveError::Occupied { player, .. } if player.number == 1
+        ));
+        let d1 = Coordinates::from_user_input("D1").unwrap();
+        assert!(matches!(
+            move_error(game.play_move(d1)),
+            MoveError::OffBoard { .. }
+        ));
+        assert_eq!(game.moves(), vec![b2]);
+        assert_eq!(game.get_current_turn_player().number, 2);
+
+        play(&mut game, "A1 B1 A2 B3");
+        let c3 = Coordinates::from_user_input("C3").unwrap();
+        assert!(matches!(
+            move_error(game.play_move(c3)),
+            MoveError::GameOver { .. }
+        ));
+    }
+
+    #[test]
+    fn maps_screen_positions_to_the_tiles_drawn_there() {
+        let game = Game::new(GameSettings {
+            num_rows: 2,
+            num_columns: 3,
+            ..GameSettings::default()
+        })
+        .unwrap();
+        let tile_at = |line, column| game.coordinates_at(line, column).map(|c| c.to_string());
+        // each tile is three columns wide, starting after the row number and two spaces
+        for (column, expected) in [(3, "A"), (5, "A"), (7, "B"), (9, "B"), (11, "C"), (13, "C")] {
+            assert_eq!(tile_at(2, column), Some(format!("{}1", expected)));
+            assert_eq!(tile_at(4, column), Some(format!("{}2", expected)));
+        }
+        // the column headers, the blank line below them, and the divider between rows
+        for line in [0, 1, 3] {
+            assert_eq!(tile_at(line, 4), None, "line {}", line);
+        }
+        // the row number, the "|" between tiles, and past either edge of the board
+        for column in [0, 1, 2, 6, 10, 14, 20] {
+            assert_eq!(tile_at(2, column), None, "column {}", column);
+        }
+        assert_eq!(tile_at(5, 4), None);
+        assert_eq!(tile_at(6, 4), None);
+    }
+
+    #[test]
+    fn rejects_blank_names_and_control_characters() {
+        for name in ["", "   ", "Bo\nb", "Bo\x1b[31mb"] {
+            assert!(Player::validate_name(name).is_err(), "{:?}", name);
+            let result = Game::new(GameSettings {
+                names: vec![name.to_string()],
+                ..GameSettings::default()
+            });
+            assert!(result.is_err(), "{:?}", name);
+        }
+        assert!(Player::validate_name("Ada ✕ Lovelace").is_ok());
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use colored::*;
+
+use tic_tac_toe::{stats, Game, Player};
+
+use crate::cli::{RecordStatsArgs, StatsArgs};
+
+// Adds the finished game to the stats, unless the players asked us not to. Stats are kept by
+// name, and anyone going by a default name like "Player 1" could be anybody, so games with such
+// a player are left out rather than lumping everyone who didn't give a name together.
+pub fn record_stats(game: &Game, args: &RecordStatsArgs) -> Result<()> {
+    if args.no_stats {
+        return Ok(());
+    }
+    if let Some(player) = game
+        .players
+        .iter()
+        .find(|player| player.name == Player::default_name(player.number))
+    {
+        println!(
+            "Left this game out of the stats, since {} has no name of their own.",
+            player
+        );
+        return Ok(());
+    }
+    stats::record_game(game, &stats_path(&args.stats_file)?)
+}
+
+// Prints every player's record and rating, best rated first.
+pub fn show_stats(args: StatsArgs) -> Result<()> {
+    let path = stats_path(&args.stats_file)?;
+    let standings = stats::load_stats(&path)?.standings();
+    if standings.is_empty() {
+        println!(
+            "No games recorded in {} yet; finish a game to get started.",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let name_width = standings
+        .iter()
+        .map(|standing| standing.name.chars().count())
+        .chain(["Player".len()])
+        .max()
+        .unwrap_or_default();
+    let header = format!(
+        "{:>4}  {:<width$}  {:>6}  {:>5}  {:>4}  {:>4}  {:>4}",
+        "Rank",
+        "Player",
+        "Rating",
+        "Games",
+        "W",
+        "D",
+        "L",
+        width = name_width
+    );
+    println!("{}", header.bold());
+    for (rank, standing) in (1..).zip(&standings) {
+        println!(
+            "{:>4}  {:<width$}  {:>6.0}  {:>5}  {:>4}  {:>4}  {:>4}",
+            rank,
+            standing.name,
+            standing.rating,
+            standing.num_games(),
+            standing.wins,
+            standing.draws,
+            standing.losses,
+            width = name_width
+        );
+    }
+    Ok(())
+}
+
+fn stats_path(stats_file: &Option<PathBuf>) -> Result<PathBuf> {
+    match stats_file {
+        Some(path) => Ok(path.clone()),
+        None => stats::default_path(),
+    }
+}
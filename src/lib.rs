@@ -0,0 +1,25 @@
+//! A tic-tac-toe engine for boards of up to 8x8 tiles, with any number of marks in a row
+//! needed to win and anywhere from two to eight players, plus computer players to play against.
+//!
+//! Set up a `Game` from `GameSettings`, then call `Game::play_move` with each player's
+//! `Coordinates` in turn until `Game::outcome` is no longer `GameOutcome::InProgress`. The
+//! `ai` module can choose moves for any player, and the `controller` module can play a whole
+//! game with any mix of people, engines and scripted moves.
+
+pub mod ai;
+mod bitboard;
+pub mod controller;
+mod coordinates;
+mod game;
+pub mod net;
+pub mod notation;
+pub mod save;
+pub mod stats;
+
+pub use coordinates::Coordinates;
+pub use game::{
+    Game, GameOutcome, GameSettings, MoveError, Notification, NotificationType, Player,
+    PlayerColor, PlayerKind,
+};
+
+pub(crate) use coordinates::Indices;
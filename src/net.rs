@@ -0,0 +1,395 @@
+//! A line-based protocol for playing a game between two processes over TCP.
+//!
+//! One side hosts: it listens for connections, owns the authoritative `Game`, and plays
+//! player 1. The first client to join plays player 2, keeping a copy of the game that it only
+//! updates with moves the host has accepted. Any number of other clients can watch, read-only.
+//! Each message is one line of text, starting with its kind in capitals:
+//!
+//! ```text
+//! JOIN [<name>]         client -> host, once: the client wants to play, optionally by name
+//! WATCH                 client -> host, once: the client wants to spectate
+//! START 3 3 3 3 1 X O   host -> client, once: protocol version, rows, columns, win length,
+//!                       first player's number, then each player's mark
+//! NAME 1 <name>         host -> client: what to call the player with the given number
+//! MOVE B2               guest -> host: a move the guest would like to make;
+//!                       host -> client: a move (by either player) that has been played
+//! ERROR <message>       host -> client: the client's last message was rejected, and why
+//! ```
+//!
+//! Right after `START`, the host sends a `NAME` for each player. Spectators who connect before
+//! the guest joins only hear the guest's `NAME` once they have, while those who connect partway
+//! through a game are sent its `START`, every `NAME` and every `MOVE` so far.
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{Coordinates, Game, GameSettings};
+
+/// Bumped whenever the protocol changes in a way older versions of the game can't follow.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// The default port to host games on.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// One line of the protocol.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Message {
+    Join(Option<String>),
+    Watch,
+    Start {
+        protocol_version: u32,
+        num_rows: usize,
+        num_columns: usize,
+        win_length: usize,
+        first_player_number: u8,
+        marks: Vec<char>,
+    },
+    Name {
+        player_number: u8,
+        name: String,
+    },
+    Move(Coordinates),
+    Error(String),
+}
+
+impl Message {
+    /// The `Start` message describing the given game, which shouldn't have any moves yet.
+    /// Players' names go in `Name` messages of their own (see `Message::names`).
+    pub fn start(game: &Game) -> Self {
+        Self::Start {
+            protocol_version: PROTOCOL_VERSION,
+            num_rows: game.num_rows(),
+            num_columns: game.num_columns(),
+            win_length: game.win_length(),
+            first_player_number: game.first_player_number(),
+            marks: game.players.iter().map(|player| player.mark).collect(),
+        }
+    }
+
+    /// A `Name` message for each of the game's players.
+    pub fn names(game: &Game) -> Vec<Self> {
+        game.players
+            .iter()
+            .map(|player| Self::Name {
+                player_number: player.number,
+                name: player.name.clone(),
+            })
+            .collect()
+    }
+
+    /// For a `Start` message, a new game with the settings it describes, every player human.
+    /// For an `Error` message, the host's reason for turning us away.
+    pub fn into_game(self) -> Result<Game> {
+        if let Self::Error(message) = self {
+            return Err(anyhow!(message));
+        }
+        let Self::Start {
+            protocol_version,
+            num_rows,
+            num_columns,
+            win_length,
+            first_player_number,
+            marks,
+        } = self
+        else {
+            return Err(anyhow!(
+                "Expected the host to start the game, but got '{}'.",
+                self
+            ));
+        };
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(anyhow!(
+                "Host speaks protocol version {}, but only version {} is supported.",
+                protocol_version,
+                PROTOCOL_VERSION
+            ));
+        }
+        Game::new(GameSettings {
+            num_rows,
+            num_columns,
+            win_length: Some(win_length),
+            marks,
+            first_player_number,
+            ..GameSettings::default()
+        })
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Join(None) => write!(f, "JOIN"),
+            Self::Join(Some(name)) => write!(f, "JOIN {}", name),
+            Self::Watch => write!(f, "WATCH"),
+            Self::Start {
+                protocol_version,
+                num_rows,
+                num_columns,
+                win_length,
+                first_player_number,
+                marks,
+            } => {
+                write!(
+                    f,
+                    "START {} {} {} {} {}",
+                    protocol_version, num_rows, num_columns, win_length, first_player_number
+                )?;
+                for mark in marks {
+                    write!(f, " {}", mark)?;
+                }
+                Ok(())
+            }
+            Self::Name {
+                player_number,
+                name,
+            } => write!(f, "NAME {} {}", player_number, name),
+            Self::Move(coords) => write!(f, "MOVE {}", coords),
+            // messages are one line each, so a multi-line error has to be flattened
+            Self::Error(message) => write!(f, "ERROR {}", message.replace('\n', " ")),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match kind {
+            "JOIN" => Ok(Self::Join((!rest.is_empty()).then(|| rest.to_string()))),
+            "WATCH" => Ok(Self::Watch),
+            "START" => {
+                let fields = rest.split_whitespace().collect::<Vec<&str>>();
+                let [version, rows, columns, win_length, first, marks @ ..] = fields.as_slice()
+                else {
+                    return Err(anyhow!("Malformed START message: '{}'.", line));
+                };
+                let parse_error = || format!("Malformed START message: '{}'.", line);
+                Ok(Self::Start {
+                    protocol_version: version.parse().with_context(parse_error)?,
+                    num_rows: rows.parse().with_context(parse_error)?,
+                    num_columns: columns.parse().with_context(parse_error)?,
+                    win_length: win_length.parse().with_context(parse_error)?,
+                    first_player_number: first.parse().with_context(parse_error)?,
+                    marks: marks
+                        .iter()
+                        .map(|mark| char::from_str(mark))
+                        .collect::<Result<Vec<char>, _>>()
+                        .with_context(parse_error)?,
+                })
+            }
+            "NAME" => {
+                let (number, name) = rest
+                    .split_once(' ')
+                    .filter(|(_, name)| !name.trim().is_empty())
+                    .ok_or_else(|| anyhow!("Malformed NAME message: '{}'.", line))?;
+                Ok(Self::Name {
+                    player_number: number
+                        .parse()
+                        .context(format!("Malformed NAME message: '{}'.", line))?,
+                    name: name.to_string(),
+                })
+            }
+            "MOVE" => Ok(Self::Move(Coordinates::from_user_input(rest)?)),
+            "ERROR" => Ok(Self::Error(rest.to_string())),
+            _ => Err(anyhow!("Unrecognized message: '{}'.", line)),
+        }
+    }
+}
+
+/// A TCP connection to the other player's process, sending and receiving `Message`s.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    /// Wraps a stream that's already connected, on either end.
+    pub fn new(stream: TcpStream) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    /// How long `receive` waits before giving up, or None to wait forever (the default).
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        Ok(self.writer.set_read_timeout(timeout)?)
+    }
+
+    /// Sends a message as a single line, failing if the other side has hung up.
+    pub fn send(&mut self, message: &Message) -> Result<()> {
+        writeln!(self.writer, "{}", message)
+            .and_then(|()| self.writer.flush())
+            .context("Lost connection to the other player.")
+    }
+
+    /// Waits for the next message, failing if the other side hangs up or sends a line that
+    /// isn't a valid message.
+    pub fn receive(&mut self) -> Result<Message> {
+        Message::from_str(&self.receive_line()?)
+    }
+
+    /// Waits for the next line, without parsing it, failing only if the other side hangs up;
+    /// useful for telling the other side what was wrong with a line, rather than giving up.
+    pub fn receive_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        let num_bytes = self
+            .reader
+            .read_line(&mut line)
+            .context("Lost connection to the other player.")?;
+        if num_bytes == 0 {
+            return Err(anyhow!("The other player disconnected."));
+        }
+        Ok(line)
+    }
+}
+
+/// Everyone watching a hosted game. Spectators only ever receive messages, and can connect at
+/// any point in the game; each new one is sent every message so far, to catch them up.
+/// Clones share the same spectators, so one can be handed to the thread accepting connections.
+#[derive(Clone, Default)]
+pub struct Spectators {
+    shared: Arc<Mutex<SpectatorsState>>,
+}
+
+#[derive(Default)]
+struct SpectatorsState {
+    history: Vec<Message>,
+    connections: Vec<Connection>,
+}
+
+impl Spectators {
+    /// Catches a new spectator up on every message so far, then sends them each one to come.
+    pub fn add(&self, mut connection: Connection) -> Result<()> {
+        let mut state = self.lock();
+        for message in &state.history {
+            connection.send(message)?;
+        }
+        state.connections.push(connection);
+        Ok(())
+    }
+
+    /// Sends the message to every spectator, dropping any who have disconnected.
+    pub fn broadcast(&self, message: Message) {
+        let mut state = self.lock();
+        state
+            .connections
+            .retain_mut(|connection| connection.send(&message).is_ok());
+        state.history.push(message);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SpectatorsState> {
+        // the state is never left half-updated, so it's still usable if a holder panicked
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn messages_round_trip_through_their_lines() {
+        let game = Game::new(GameSettings {
+            num_rows: 4,
+            num_columns: 5,
+            win_length: Some(3),
+            marks: vec!['X', 'O', '✕'],
+            first_player_number: 2,
+            ..GameSettings::default()
+        })
+        .unwrap();
+        let messages = [
+            Message::Join(None),
+            Message::Join(Some("Ada Lovelace".to_string())),
+            Message::Watch,
+            Message::start(&game),
+            Message::Name {
+                player_number: 2,
+                name: "Grace Hopper".to_string(),
+            },
+            Message::Move(Coordinates::from_user_input("E4").unwrap()),
+            Message::Error("Tile B2 is already occupied by Player 1.".to_string()),
+        ];
+        for message in messages {
+            let line = format!("{}\n", message);
+            assert_eq!(Message::from_str(&line).unwrap(), message, "{}", line);
+        }
+        assert_eq!(
+            Message::start(&game).to_string(),
+            format!("START {} 4 5 3 2 X O ✕", PROTOCOL_VERSION)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for line in [
+            "START 3 3 3",
+            "START 3 three 3 3 1 X O",
+            "START 3 3 3 3 1 XO",
+            "NAME 1",
+            "NAME 1  ",
+            "NAME one Ada",
+            "MOVE",
+            "MOVE nine",
+            "HELLO",
+            "",
+        ] {
+            assert!(Message::from_str(line).is_err(), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn starts_games_only_from_the_same_protocol_version() {
+        let game = Game::new(GameSettings::default()).unwrap();
+        let started_game = Message::start(&game).into_game().unwrap();
+        assert_eq!(started_game.num_rows(), 3);
+        assert_eq!(started_game.players.len(), 2);
+
+        let old_start = Message::Start {
+            protocol_version: PROTOCOL_VERSION - 1,
+            num_rows: 3,
+            num_columns: 3,
+            win_length: 3,
+            first_player_number: 1,
+            marks: vec!['X', 'O'],
+        };
+        assert!(old_start.into_game().is_err());
+        let error = Message::Error("No room.".to_string()).into_game();
+        assert_eq!(error.err().unwrap().to_string(), "No room.");
+    }
+
+    #[test]
+    fn connections_carry_messages_over_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            let mut connection = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+            connection.send(&Message::Join(None)).unwrap();
+            let reply = connection.receive().unwrap();
+            connection.send(&Message::Watch).unwrap();
+            reply
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let mut host = Connection::new(stream).unwrap();
+        assert_eq!(host.receive().unwrap(), Message::Join(None));
+        let coords = Coordinates::from_user_input("B2").unwrap();
+        host.send(&Message::Move(coords)).unwrap();
+        assert_eq!(host.receive_line().unwrap(), "WATCH\n");
+        assert_eq!(guest.join().unwrap(), Message::Move(coords));
+        // the guest hung up once it was done
+        assert!(host.receive().is_err());
+    }
+}
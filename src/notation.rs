@@ -0,0 +1,519 @@
+//! A portable text notation for game records, loosely modeled on chess's PGN.
+//!
+//! A record starts with a header section of `[Tag "value"]` lines, followed by a blank line
+//! and then every move in order, written the way players type them and separated by spaces
+//! (line breaks are fine too). For example:
+//!
+//! ```text
+//! [Date "2026.10.16"]
+//! [Rows "3"]
+//! [Columns "3"]
+//! [WinLength "3"]
+//! [FirstPlayer "1"]
+//! [Player1 "Player 1"]
+//! [Mark1 "X"]
+//! [Player2 "Player 2"]
+//! [Mark2 "O"]
+//! [Result "1"]
+//!
+//! B2 A1 C1 A3 A2 C2 B3 B1 C3
+//! ```
+//!
+//! `Rows` and `Columns` are required. `WinLength`, `FirstPlayer` and the `Mark` tags default
+//! to the same values as `GameSettings`, and unknown tags are ignored. `Result` is the number of
+//! the winning player, `draw`, or `*` for a game that isn't over. As in PGN, a `"` or `\`
+//! within a value is escaped with a backslash, e.g. `[Player1 "Al \"X\""]`. A file can hold
+//! any number of records, one after another.
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+
+use crate::{Coordinates, Game, GameOutcome, GameSettings, Player};
+
+/// A finished (or unfinished) game, as written in the notation.
+pub struct GameRecord {
+    pub date: Option<String>,
+    pub num_rows: usize,
+    pub num_columns: usize,
+    pub win_length: usize,
+    pub first_player_number: u8,
+    /// (display name, mark) for each player, in turn order.
+    pub players: Vec<(String, char)>,
+    pub result: RecordResult,
+    pub moves: Vec<Coordinates>,
+}
+
+/// How a recorded game ended, if it did.
+#[derive(PartialEq, Clone, Copy)]
+pub enum RecordResult {
+    Unfinished,
+    Draw,
+    Victory { player_number: u8 },
+}
+
+impl GameRecord {
+    /// Records the game as it stands, dated today.
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            date: Some(chrono::Local::now().format("%Y.%m.%d").to_string()),
+            num_rows: game.num_rows(),
+            num_columns: game.num_columns(),
+            win_length: game.win_length(),
+            first_player_number: game.first_player_number(),
+            players: game
+                .players
+                .iter()
+                .map(|player| (player.to_string(), player.mark))
+                .collect(),
+            result: RecordResult::from_outcome(game.outcome()),
+            moves: game.moves(),
+        }
+    }
+
+    /// Rebuilds the recorded game by replaying its moves, failing if any of them is illegal or
+    /// if the result doesn't match the recorded one. Every player in the rebuilt game is human.
+    pub fn into_game(self) -> Result<Game> {
+        let mut game = Game::new(GameSettings {
+            num_rows: self.num_rows,
+            num_columns: self.num_columns,
+            win_length: Some(self.win_length),
+            marks: self.players.iter().map(|&(_, mark)| mark).collect(),
+            names: self.players.iter().map(|(name, _)| name.clone()).collect(),
+            first_player_number: self.first_player_number,
+            ..GameSettings::default()
+        })?;
+        for (move_index, &coords) in self.moves.iter().enumerate() {
+            game.play_move(coords).context(format!(
+                "Could not replay move {} ({}) of game record.",
+                move_index + 1,
+                coords
+            ))?;
+        }
+        // a finished game leaves its last notification behind; it shouldn't show up on import
+        game.take_notification();
+        if RecordResult::from_outcome(game.outcome()) != self.result {
+            return Err(anyhow!(
+                "Game record's result is '{}', but its moves end in '{}'.",
+                self.result,
+                RecordResult::from_outcome(game.outcome())
+            ));
+        }
+        Ok(game)
+    }
+
+    // Parses a single record's header and move lines, which must not be empty.
+    fn parse(lines: &[&str]) -> Result<Self> {
+        let tag_re = Regex::new(r#"^\[\s*(\w+)\s+"((?:[^"\\]|\\.)*)"\s*\]$"#)?;
+        let mut record = Self {
+            date: None,
+            num_rows: 0,
+            num_columns: 0,
+            win_length: 0,
+            first_player_number: GameSettings::default().first_player_number,
+            players: vec![],
+            result: RecordResult::Unfinished,
+            moves: vec![],
+        };
+        let (mut num_rows, mut num_columns, mut win_length) = (None, None, None);
+        let mut marks: Vec<Option<char>> = GameSettings::default()
+            .marks
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut names: Vec<Option<String>> = vec![];
+        for line in lines {
+            if !line.starts_with('[') {
+                for token in line.split_whitespace() {
+                    record.moves.push(Coordinates::from_user_input(token)?);
+                }
+                continue;
+            }
+            let cap = tag_re
+                .captures(line)
+                .context(format!("Could not parse '{}' as a tag.", line))?;
+            let (tag, value) = (&cap[1], unescape(&cap[2]));
+            let parse_number = || {
+                usize::from_str(&value).context(format!(
+                    "Tag {} should be a number, but is '{}'.",
+                    tag, value
+                ))
+            };
+            match tag {
+                "Date" => record.date = Some(value.clone()),
+                "Rows" => num_rows = Some(parse_number()?),
+                "Columns" => num_columns = Some(parse_number()?),
+                "WinLength" => win_length = Some(parse_number()?),
+                "FirstPlayer" => {
+                    record.first_player_number = u8::from_str(&value).context(format!(
+                        "Tag {} should be a player number, but is '{}'.",
+                        tag, value
+                    ))?
+                }
+                "Result" => record.result = RecordResult::from_str(&value)?,
+                _ => {
+                    // per-player tags end in the player's number, e.g. Mark2
+                    let Some((prefix @ ("Player" | "Mark"), number)) = tag
+                        .find(|c: char| c.is_ascii_digit())
+                        .map(|i| tag.split_at(i))
+                    else {
+                        continue;
+                    };
+                    let player_index = parse_number_from_tag(tag, number)?;
+                    if prefix == "Mark" {
+                        let mut chars = value.chars();
+                        let (Some(mark), None) = (chars.next(), chars.next()) else {
+                            return Err(anyhow!("Tag {} should be a single character.", tag));
+                        };
+                        if marks.len() <= player_index {
+                            marks.resize(player_index + 1, None);
+                        }
+                        marks[player_index] = Some(mark);
+                    } else {
+                        if names.len() <= player_index {
+                            names.resize(player_index + 1, None);
+                        }
+                        names[player_index] = Some(value);
+                    }
+                }
+            }
+        }
+
+        record.num_rows = num_rows.context("Game record is missing its Rows tag.")?;
+        record.num_columns = num_columns.context("Game record is missing its Columns tag.")?;
+        record.win_length = win_length.unwrap_or(record.num_rows.min(record.num_columns));
+        names.resize(marks.len(), None);
+        record.players = marks
+            .into_iter()
+            .zip(names)
+            .zip(1..)
+            .map(|((mark, name), number)| {
+                let mark =
+                    mark.context(format!("Game record is missing its Mark{} tag.", number))?;
+                Ok((name.unwrap_or_else(|| Player::default_name(number)), mark))
+            })
+            .collect::<Result<Vec<(String, char)>>>()?;
+        Ok(record)
+    }
+}
+
+// turns the "2" of e.g. "Mark2" into the zero-indexed player index 1; numbers past the most
+// players a game can have are rejected before any per-player list is grown to fit them
+fn parse_number_from_tag(tag: &str, number: &str) -> Result<usize> {
+    usize::from_str(number)
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .filter(|&player_index| player_index < Game::MAX_NUM_PLAYERS)
+        .context(format!(
+            "Tag {} doesn't refer to a valid player number.",
+            tag
+        ))
+}
+
+// escapes the quotes and backslashes in a tag value, so the value can be quoted
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// the reverse of `escape`: a backslash means the character after it is part of the value
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(char),
+        }
+    }
+    unescaped
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(date) = &self.date {
+            writeln!(f, "[Date \"{}\"]", escape(date))?;
+        }
+        writeln!(f, "[Rows \"{}\"]", self.num_rows)?;
+        writeln!(f, "[Columns \"{}\"]", self.num_columns)?;
+        writeln!(f, "[WinLength \"{}\"]", self.win_length)?;
+        writeln!(f, "[FirstPlayer \"{}\"]", self.first_player_number)?;
+        for ((name, mark), number) in self.players.iter().zip(1..) {
+            writeln!(f, "[Player{} \"{}\"]", number, escape(name))?;
+            writeln!(f, "[Mark{} \"{}\"]", number, escape(&mark.to_string()))?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f)?;
+        let moves = self
+            .moves
+            .iter()
+            .map(Coordinates::to_string)
+            .collect::<Vec<String>>();
+        writeln!(f, "{}", moves.join(" "))
+    }
+}
+
+impl RecordResult {
+    fn from_outcome(outcome: GameOutcome) -> Self {
+        match outcome {
+            GameOutcome::InProgress => Self::Unfinished,
+            GameOutcome::Draw => Self::Draw,
+            GameOutcome::Victory(player) => Self::Victory {
+                player_number: player.number,
+            },
+        }
+    }
+}
+
+impl fmt::Display for RecordResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unfinished => write!(f, "*"),
+            Self::Draw => write!(f, "draw"),
+            Self::Victory { player_number } => write!(f, "{}", player_number),
+        }
+    }
+}
+
+impl FromStr for RecordResult {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        match input {
+            "*" => Ok(Self::Unfinished),
+            "draw" => Ok(Self::Draw),
+            _ => u8::from_str(input)
+                .map(|player_number| Self::Victory { player_number })
+                .context(format!(
+                    "Result should be a player number, 'draw' or '*', but is '{}'.",
+                    input
+                )),
+        }
+    }
+}
+
+/// Parses every game record in the text, in order.
+pub fn parse_records(text: &str) -> Result<Vec<GameRecord>> {
+    let mut records = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut seen_tags: Vec<&str> = Vec::new();
+    let mut in_moves = false;
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let maybe_tag = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_whitespace().next());
+        // a tag after some moves must be the start of the next record, as must a tag the
+        // current record already has (in case a record has no moves at all)
+        if let Some(tag) = maybe_tag {
+            if in_moves || seen_tags.contains(&tag) {
+                records.push(GameRecord::parse(&lines)?);
+                lines.clear();
+                seen_tags.clear();
+            }
+            seen_tags.push(tag);
+        }
+        in_moves = maybe_tag.is_none();
+        lines.push(line);
+    }
+    if !lines.is_empty() {
+        records.push(GameRecord::parse(&lines)?);
+    }
+    Ok(records)
+}
+
+/// Appends the game's record to the given file, creating the file if need be.
+pub fn export_game(game: &Game, path: &Path) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Could not open {}.", path.display()))?;
+    // keep records in the same file separated by a blank line
+    let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+    write!(file, "{}{}", separator, GameRecord::from_game(game))
+        .context(format!("Could not write to {}.", path.display()))
+}
+
+/// Reads the game records in the given file, in order.
+pub fn read_records(path: &Path) -> Result<Vec<GameRecord>> {
+    let text = fs::read_to_string(path).context(format!("Could not read {}.", path.display()))?;
+    parse_records(&text).context(format!(
+        "Could not parse game records in {}.",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_with_moves(settings: GameSettings, moves: &str) -> Game {
+        let mut game = Game::new(settings).unwrap();
+        for input in moves.split_whitespace() {
+            game.play_move(Coordinates::from_user_input(input).unwrap())
+                .unwrap();
+        }
+        game
+    }
+
+    fn parse_error(text: &str) -> String {
+        match parse_records(text) {
+            Ok(_) => panic!("expected {:?} not to parse", text),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_display() {
+        let game = game_with_moves(
+            GameSettings {
+                num_rows: 4,
+                num_columns: 5,
+                win_length: Some(3),
+                marks: vec!['✕', 'O', 'V'],
+                names: vec!["Ada Lovelace".to_string(), "Grace".to_string()],
+                first_player_number: 2,
+                ..GameSettings::default()
+            },
+            "A1 B1 C1 A2 B2 C2 A3",
+        );
+        let text = GameRecord::from_game(&game).to_string();
+        let records = parse_records(&text).unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!((record.num_rows, record.num_columns), (4, 5));
+        assert_eq!(record.win_length, 3);
+        assert_eq!(record.first_player_number, 2);
+        assert_eq!(
+            record.players,
+            vec![
+                ("Ada Lovelace".to_string(), '✕'),
+                ("Grace".to_string(), 'O'),
+                ("Player 3".to_string(), 'V'),
+            ]
+        );
+        // O moves first, and completes column A
+        assert!(record.result == RecordResult::Victory { player_number: 2 });
+        assert_eq!(record.moves, game.moves());
+
+        // the rebuilt game prints exactly the same record
+        let rebuilt = records.into_iter().next().unwrap().into_game().unwrap();
+        assert_eq!(GameRecord::from_game(&rebuilt).to_string(), text);
+    }
+
+    #[test]
+    fn escapes_quotes_and_backslashes_in_tag_values() {
+        let game = game_with_moves(
+            GameSettings {
+                marks: vec!['"', '\\'],
+                names: vec!["Al \"X\"".to_string(), "C:\\Bob\\".to_string()],
+                ..GameSettings::default()
+            },
+            "B2 A1",
+        );
+        let text = GameRecord::from_game(&game).to_string();
+        assert!(text.contains(r#"[Player1 "Al \"X\""]"#));
+        assert!(text.contains(r#"[Mark2 "\\"]"#));
+        let record = parse_records(&text).unwrap().into_iter().next().unwrap();
+        assert_eq!(
+            record.players,
+            vec![
+                ("Al \"X\"".to_string(), '"'),
+                ("C:\\Bob\\".to_string(), '\\')
+            ]
+        );
+        assert_eq!(record.into_game().unwrap().moves(), game.moves());
+    }
+
+    #[test]
+    fn parses_every_record_in_a_file() {
+        let won = game_with_moves(GameSettings::default(), "A1 B1 A2 B2 A3");
+        let drawn = game_with_moves(GameSettings::default(), "B2 A1 C1 A3 A2 C2 B1 B3 C3");
+        let unfinished = game_with_moves(GameSettings::default(), "C3");
+        // the same way `export_game` separates them
+        let text = [&won, &drawn, &unfinished]
+            .map(|game| GameRecord::from_game(game).to_string())
+            .join("\n");
+        let records = parse_records(&text).unwrap();
+        let results = records
+            .iter()
+            .map(|record| (record.result, record.moves.len()))
+            .collect::<Vec<_>>();
+        assert!(
+            results
+                == vec![
+                    (RecordResult::Victory { player_number: 1 }, 5),
+                    (RecordResult::Draw, 9),
+                    (RecordResult::Unfinished, 1),
+                ]
+        );
+    }
+
+    #[test]
+    fn parses_records_without_moves() {
+        let text =
+            "[Rows \"3\"]\n[Columns \"3\"]\n\n[Rows \"4\"]\n[Columns \"4\"]\n[Result \"*\"]\n";
+        let records = parse_records(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.moves.is_empty()));
+        assert_eq!(records[1].num_rows, 4);
+        assert_eq!(
+            records[0].players,
+            vec![("Player 1".to_string(), 'X'), ("Player 2".to_string(), 'O')]
+        );
+        assert_eq!(parse_records("").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_records() {
+        assert!(parse_error("[Rows 3]\n[Columns \"3\"]").contains("as a tag"));
+        assert!(parse_error("[Rows \"3\"\n[Columns \"3\"]").contains("as a tag"));
+        assert!(parse_error("[Rows \"three\"]\n[Columns \"3\"]").contains("should be a number"));
+        assert!(parse_error("[Columns \"3\"]\n\nB2").contains("missing its Rows tag"));
+        assert!(parse_error("[Rows \"3\"]\n[Columns \"3\"]\n[Mark1 \"XY\"]")
+            .contains("single character"));
+        assert!(
+            parse_error("[Rows \"3\"]\n[Columns \"3\"]\n[Player0 \"Ada\"]")
+                .contains("valid player number")
+        );
+        for tag in [
+            "Mark9",
+            "Player9",
+            "Mark4000000000000",
+            "Mark99999999999999999999",
+        ] {
+            let text = format!("[Rows \"3\"]\n[Columns \"3\"]\n[{} \"V\"]", tag);
+            assert!(
+                parse_error(&text).contains("valid player number"),
+                "{}",
+                tag
+            );
+        }
+        assert!(
+            parse_error("[Rows \"3\"]\n[Columns \"3\"]\n[FirstPlayer \"257\"]")
+                .contains("should be a player number")
+        );
+        assert!(parse_error("[Rows \"3\"]\n[Columns \"3\"]\n[Mark4 \"V\"]")
+            .contains("missing its Mark3 tag"));
+        assert!(
+            parse_error("[Rows \"3\"]\n[Columns \"3\"]\n[Result \"won\"]")
+                .contains("Result should be")
+        );
+        assert!(parse_error("[Rows \"3\"]\n[Columns \"3\"]\n\nB2 nine").contains("coordinates"));
+    }
+
+    #[test]
+    fn rejects_records_whose_moves_do_not_match_their_result() {
+        let text = "[Rows \"3\"]\n[Columns \"3\"]\n[Result \"2\"]\n\nA1 B1 A2 B2 A3";
+        let record = parse_records(text).unwrap().into_iter().next().unwrap();
+        assert!(record.into_game().is_err());
+        let text = "[Rows \"3\"]\n[Columns \"3\"]\n\nA1 A1";
+        let record = parse_records(text).unwrap().into_iter().next().unwrap();
+        assert!(record.into_game().is_err());
+    }
+}
@@ -0,0 +1,379 @@
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+
+use tic_tac_toe::controller::{PlayerController, StdinController};
+use tic_tac_toe::net::{Connection, Message, Spectators};
+use tic_tac_toe::{Coordinates, Game, GameOutcome, Notification, NotificationType, Player};
+
+use crate::cli::{GuestArgs, HostArgs, JoinArgs};
+use crate::leaderboard::record_stats;
+use crate::{handle_error, show_game};
+
+// how long the host waits for a new connection to say whether it's joining or watching
+const GREETING_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Which end of the connection this process is. The host's copy of the game is the one that
+// counts: it checks every move, and the guest only plays moves the host has accepted.
+#[derive(Clone, Copy)]
+enum Role<'a> {
+    Host(&'a Spectators),
+    Guest,
+}
+
+impl Role<'_> {
+    fn player_number(self) -> u8 {
+        match self {
+            Role::Host(_) => 1,
+            Role::Guest => 2,
+        }
+    }
+}
+
+pub fn host(args: HostArgs) -> Result<()> {
+    let mut game = Game::new(args.settings.game_settings())?;
+    if game.players.len() != 2 {
+        return Err(anyhow!(
+            "Networked games are for exactly two players, but {} marks were given.",
+            game.players.len()
+        ));
+    }
+    let listener = TcpListener::bind(("0.0.0.0", args.port))
+        .context(format!("Could not listen on port {}.", args.port))?;
+    let spectators = Spectators::default();
+    spectators.broadcast(Message::start(&game));
+    // the guest's name isn't known until they join
+    let [host_name, _] = Message::names(&game).try_into().unwrap(); // two players, checked above
+    spectators.broadcast(host_name);
+    // connections keep coming in for the whole game, since spectators can join at any time
+    let (guest_sender, guest_receiver) = mpsc::channel();
+    let visitor_spectators = spectators.clone();
+    let host_player_name = game.players[0].name.clone();
+    thread::spawn(move || {
+        welcome_visitors(listener, guest_sender, visitor_spectators, host_player_name)
+    });
+
+    println!(
+        "Hosting on port {}; waiting for player 2 to join...",
+        args.port
+    );
+    let (mut connection, address, guest_name) = guest_receiver.recv()?;
+    if let Some(guest_name) = guest_name {
+        game.players[1].name = guest_name;
+    }
+    connection.send(&Message::start(&game))?;
+    let [host_name, guest_name] = Message::names(&game).try_into().unwrap();
+    connection.send(&host_name)?;
+    connection.send(&guest_name)?;
+    spectators.broadcast(guest_name);
+    game.set_notification(Notification {
+        message: format!("{} joined from {}.", game.players[1], address),
+        notification_type: NotificationType::Info,
+    });
+    play_online(&mut game, &mut connection, Role::Host(&spectators))?;
+    record_stats(&game, &args.stats)
+}
+
+pub fn join(args: GuestArgs) -> Result<()> {
+    let (mut connection, mut game) = connect(&args.join.address, Message::Join(args.name))?;
+    // the host names both players before anything else happens, so we know who we're playing
+    for _ in 0..game.players.len() {
+        match connection.receive()? {
+            Message::Name {
+                player_number,
+                name,
+            } => rename(&mut game, player_number, name)?,
+            message => return Err(unexpected(message)),
+        }
+    }
+    game.set_notification(Notification {
+        message: format!("Joined game at {}.", args.join.address),
+        notification_type: NotificationType::Info,
+    });
+    // only the host adds the game to its stats, so a game hosted and joined on the same
+    // machine isn't counted twice
+    play_online(&mut game, &mut connection, Role::Guest)
+}
+
+// Shows the hosted game live, move by move, without being able to make any moves.
+pub fn watch(args: JoinArgs) -> Result<()> {
+    let (mut connection, mut game) = connect(&args.address, Message::Watch)?;
+    game.set_notification(Notification {
+        message: format!("Watching game at {}.", args.address),
+        notification_type: NotificationType::Info,
+    });
+    while game.outcome() == GameOutcome::InProgress {
+        show_game(&mut game)?;
+        let current_player = game.get_current_turn_player();
+        println!("Waiting for {} to move...", current_player);
+        match connection.receive()? {
+            // the host only sends moves it has already played, so they can't be illegal
+            Message::Move(coords) => {
+                game.play_move(coords)?;
+                announce_move(&mut game, current_player, coords);
+            }
+            // names can arrive partway through, if we started watching before the guest joined
+            Message::Name {
+                player_number,
+                name,
+            } => rename(&mut game, player_number, name)?,
+            message => return Err(unexpected(message)),
+        }
+    }
+    // show the final board, with the game's result
+    show_game(&mut game)
+}
+
+// Connects to the host, introduces ourselves with the given message (joining or watching),
+// and sets up the game the host sends back.
+fn connect(address: &str, greeting: Message) -> Result<(Connection, Game)> {
+    let stream =
+        TcpStream::connect(address).context(format!("Could not connect to {}.", address))?;
+    let mut connection = Connection::new(stream)?;
+    connection.send(&greeting)?;
+    let game = connection.receive()?.into_game()?;
+    Ok((connection, game))
+}
+
+// Sets the name of the player with the given number, as told by the host.
+fn rename(game: &mut Game, player_number: u8, name: String) -> Result<()> {
+    Player::validate_name(&name).context(format!(
+        "The host named Player {} something no player can be called.",
+        player_number
+    ))?;
+    let player = game
+        .players
+        .iter_mut()
+        .find(|player| player.number == player_number)
+        .ok_or_else(|| {
+            anyhow!(
+                "The host named a Player {} who isn't in the game.",
+                player_number
+            )
+        })?;
+    player.name = name;
+    Ok(())
+}
+
+// the guest's connection, address, and the name they asked to play under, if any
+type Guest = (Connection, SocketAddr, Option<String>);
+
+// Greets everyone who connects to the host: the first to ask to join plays as player 2, and
+// anyone else can watch.
+fn welcome_visitors(
+    listener: TcpListener,
+    guest_sender: Sender<Guest>,
+    spectators: Spectators,
+    host_name: String,
+) {
+    let mut guest_sender = Some(guest_sender);
+    // one visitor's failure to connect properly is no reason to turn away the rest
+    for stream in listener.incoming().flatten() {
+        let _ = greet(stream, &mut guest_sender, &spectators, &host_name);
+    }
+}
+
+// The guest can play under any name a player could have (see `Player::validate_name`), except
+// for the host's.
+fn check_guest_name(name: &str, host_name: &str) -> Result<()> {
+    Player::validate_name(name)?;
+    if name == host_name {
+        return Err(anyhow!("'{}' is already the host's name.", name));
+    }
+    Ok(())
+}
+
+fn greet(
+    stream: TcpStream,
+    guest_sender: &mut Option<Sender<Guest>>,
+    spectators: &Spectators,
+    host_name: &str,
+) -> Result<()> {
+    let address = stream.peer_addr()?;
+    let mut connection = Connection::new(stream)?;
+    // a visitor who never says anything shouldn't hold up everyone who connects after them
+    connection.set_read_timeout(Some(GREETING_TIMEOUT))?;
+    let greeting = connection.receive()?;
+    connection.set_read_timeout(None)?;
+    // turning away a guest's name leaves the game open for them to try another
+    if let Message::Join(Some(name)) = &greeting {
+        if let Err(error) = check_guest_name(name, host_name) {
+            return connection.send(&Message::Error(format!(
+                "{} Pick another with --name.",
+                error
+            )));
+        }
+    }
+    match greeting {
+        Message::Join(name) => match guest_sender.take() {
+            Some(sender) => Ok(sender.send((connection, address, name))?),
+            None => connection.send(&Message::Error(
+                "This game already has two players; try `watch` instead.".to_string(),
+            )),
+        },
+        Message::Watch => spectators.add(connection),
+        greeting => connection.send(&Message::Error(format!(
+            "Expected JOIN or WATCH, but got '{}'.",
+            greeting
+        ))),
+    }
+}
+
+fn play_online(game: &mut Game, connection: &mut Connection, role: Role) -> Result<()> {
+    while game.outcome() == GameOutcome::InProgress {
+        show_game(game)?;
+        let current_player = game.get_current_turn_player();
+        if current_player.number == role.player_number() {
+            let coords = StdinController.choose_move(game)?;
+            play_local_move(game, connection, role, coords)?;
+            continue;
+        }
+
+        println!("Waiting for {} to move...", current_player);
+        let coords = match role {
+            Role::Host(spectators) => {
+                let Some(coords) = receive_guest_move(game, connection)? else {
+                    continue;
+                };
+                connection.send(&Message::Move(coords))?;
+                spectators.broadcast(Message::Move(coords));
+                coords
+            }
+            // the host only sends moves it has already played, so they can't be illegal
+            Role::Guest => match connection.receive()? {
+                Message::Move(coords) => {
+                    game.play_move(coords)?;
+                    coords
+                }
+                message => return Err(unexpected(message)),
+            },
+        };
+        announce_move(game, current_player, coords);
+    }
+    // show the final board, with the game's result
+    show_game(game)
+}
+
+// The host plays its own moves and tells everyone else about them; the guest has to ask the
+// host to play its moves, and only plays them once the host accepts.
+fn play_local_move(
+    game: &mut Game,
+    connection: &mut Connection,
+    role: Role,
+    coords: Coordinates,
+) -> Result<()> {
+    match role {
+        Role::Host(spectators) => match game.play_move(coords) {
+            Ok(()) => {
+                connection.send(&Message::Move(coords))?;
+                spectators.broadcast(Message::Move(coords));
+            }
+            Err(error) => handle_error(game, error, Some(coords)),
+        },
+        Role::Guest => {
+            connection.send(&Message::Move(coords))?;
+            match connection.receive()? {
+                Message::Move(accepted_coords) => game.play_move(accepted_coords)?,
+                Message::Error(message) => handle_error(game, anyhow!(message), Some(coords)),
+                message => return Err(unexpected(message)),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Waits for the guest's next message and plays the move it asks for, returning its
+// coordinates. Anything but a legal move is the guest's problem rather than a reason to end
+// the game, so the guest is sent an error saying what was wrong, and None is returned.
+fn receive_guest_move(game: &mut Game, connection: &mut Connection) -> Result<Option<Coordinates>> {
+    let line = connection.receive_line()?;
+    let result = match Message::from_str(&line) {
+        Ok(Message::Move(coords)) => game.play_move(coords).map(|()| coords),
+        Ok(message) => Err(anyhow!("Expected MOVE, but got '{}'.", message)),
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(coords) => Ok(Some(coords)),
+        Err(error) => {
+            connection.send(&Message::Error(error.to_string()))?;
+            Ok(None)
+        }
+    }
+}
+
+// Leaves a notification saying who just moved where, for moves made on another machine.
+fn announce_move(game: &mut Game, player: Player, coords: Coordinates) {
+    // if the move ended the game, the game's own notification says so instead
+    if game.outcome() == GameOutcome::InProgress {
+        game.set_notification(Notification {
+            message: format!("{} played {}.", player, coords),
+            notification_type: NotificationType::Info,
+        });
+    }
+}
+
+fn unexpected(message: Message) -> anyhow::Error {
+    anyhow!("Unexpected message from the other player: '{}'.", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+
+    use tic_tac_toe::GameSettings;
+
+    use super::*;
+
+    #[test]
+    fn host_answers_bad_guest_messages_with_errors_and_keeps_waiting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            // gibberish, the wrong kind of message and a tile the host already took each get an
+            // error back, and then a move that's fine gets played
+            let mut replies = vec![];
+            for line in ["HELLO", "WATCH", "MOVE B2"] {
+                writeln!(stream, "{}", line).unwrap();
+                let mut reply = String::new();
+                reader.read_line(&mut reply).unwrap();
+                replies.push(Message::from_str(&reply).unwrap());
+            }
+            writeln!(stream, "MOVE C3").unwrap();
+            replies
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let mut connection = Connection::new(stream).unwrap();
+        let mut game = Game::new(GameSettings::default()).unwrap();
+        game.play_move(Coordinates::from_user_input("B2").unwrap())
+            .unwrap();
+
+        let mut num_rejected = 0;
+        let coords = loop {
+            match receive_guest_move(&mut game, &mut connection).unwrap() {
+                Some(coords) => break coords,
+                None => num_rejected += 1,
+            }
+        };
+        assert_eq!(coords.to_string(), "C3");
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(num_rejected, 3);
+        let replies = guest.join().unwrap();
+        assert!(replies
+            .iter()
+            .all(|reply| matches!(reply, Message::Error(_))));
+    }
+
+    #[test]
+    fn guests_need_a_valid_name_of_their_own() {
+        assert!(check_guest_name("Grace", "Ada").is_ok());
+        for name in ["Ada", " ", "Bo\x1b[31mb"] {
+            assert!(check_guest_name(name, "Ada").is_err(), "{:?}", name);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use colored::*;
+
+use tic_tac_toe::{save, Game};
+
+use crate::cli::ReplayArgs;
+use crate::import_game;
+
+// Steps through a recorded or saved game one move at a time, starting from the empty board.
+// The game's own undo and redo do the stepping, so the latest move and any winning line are
+// highlighted just as they were when the game was played.
+pub fn replay(args: ReplayArgs) -> Result<()> {
+    let mut game = load_game(&args.file, args.game_number)?;
+    let num_moves = game.moves().len();
+    while game.can_undo() {
+        game.undo()?;
+    }
+
+    loop {
+        clearscreen::clear()?;
+        let move_number = game.moves().len();
+        println!(); // newline to ensure a command-line prompt doesn't skew first line of game board
+        println!("{}", game.render_board());
+        match game.moves().last() {
+            Some(coords) => println!(
+                "Move {} of {}: {} played {}.",
+                move_number,
+                num_moves,
+                game.players[mover_index(&game, move_number)],
+                coords
+            ),
+            None => println!("Start of game; {} moves recorded.", num_moves),
+        }
+        // only the final move leaves a notification, announcing the result
+        if let Some(notification) = game.take_notification() {
+            println!("{}", notification);
+        }
+        println!();
+        print!(
+            "{} to step forward, or enter 'b' (back), 's' (start), 'e' (end), a move number, or 'q' (quit): ",
+            "Press Enter".bold()
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(());
+        }
+        let target_move_number = match input.trim().to_lowercase().as_str() {
+            "" | "n" => move_number + 1,
+            "b" => move_number.saturating_sub(1),
+            "s" => 0,
+            "e" => num_moves,
+            "q" => return Ok(()),
+            other => other.parse().unwrap_or(move_number),
+        };
+        let target_move_number = target_move_number.min(num_moves);
+        while game.moves().len() > target_move_number {
+            game.undo()?;
+        }
+        while game.moves().len() < target_move_number {
+            game.redo()?;
+        }
+    }
+}
+
+// JSON files are games saved with the `save` command; anything else is a file of game records
+fn load_game(path: &Path, game_number: usize) -> Result<Game> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        save::load_game(path)
+    } else {
+        import_game(path, game_number)
+    }
+}
+
+// the index in `Game::players` of the player who made the given (one-indexed) move; every
+// move but the last advances the turn to the next player in order
+fn mover_index(game: &Game, move_number: usize) -> usize {
+    let first_player_index = usize::from(game.first_player_number()) - 1;
+    (first_player_index + move_number - 1) % game.players.len()
+}
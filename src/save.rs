@@ -0,0 +1,284 @@
+//! Saving games to disk and loading them back.
+//!
+//! Games are saved as JSON, with one field per `SavedGame` field. For example, here's a game
+//! of classic tic-tac-toe against the computer, two moves in:
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "num_rows": 3,
+//!   "num_columns": 3,
+//!   "win_length": 3,
+//!   "players": [
+//!     { "number": 1, "name": "Ada", "mark": "X", "color": "cyan", "kind": "human" },
+//!     {
+//!       "number": 2,
+//!       "name": "Computer (minimax, perfect)",
+//!       "mark": "O",
+//!       "color": "magenta",
+//!       "kind": { "computer": { "minimax": "perfect" } }
+//!     }
+//!   ],
+//!   "first_player_number": 1,
+//!   "moves": ["B2", "A1"],
+//!   "turn_number": 3,
+//!   "outcome": "in_progress"
+//! }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{Coordinates, Game, GameOutcome, GameSettings, Player, PlayerColor, PlayerKind};
+
+/// Everything needed to pick a game back up where it left off. Loading a saved game replays
+/// its moves on a fresh board, so the board itself isn't saved; `turn_number` and `outcome` are
+/// saved anyway, to catch files that were edited into an inconsistent state.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    /// Bumped whenever the format changes in a way older versions of the game can't read.
+    pub format_version: u32,
+    pub num_rows: usize,
+    pub num_columns: usize,
+    pub win_length: usize,
+    /// In turn order.
+    pub players: Vec<Player>,
+    pub first_player_number: u8,
+    /// Every move so far, oldest first, in the same format players type them, e.g. "B2".
+    pub moves: Vec<String>,
+    pub turn_number: usize,
+    pub outcome: SavedOutcome,
+}
+
+/// `GameOutcome`, with the winner saved as just their player number.
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedOutcome {
+    InProgress,
+    Draw,
+    Victory { player_number: u8 },
+}
+
+impl SavedGame {
+    /// The version of the format written by `save_game`; `load_game` rejects any other.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Captures everything needed to pick the game back up where it is now.
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            num_rows: game.num_rows(),
+            num_columns: game.num_columns(),
+            win_length: game.win_length(),
+            players: game.players.clone(),
+            first_player_number: game.first_player_number(),
+            moves: game.moves().iter().map(Coordinates::to_string).collect(),
+            turn_number: game.turn_number(),
+            outcome: SavedOutcome::from_outcome(game.outcome()),
+        }
+    }
+
+    /// Rebuilds the saved game by replaying its moves, failing if any of them is illegal or
+    /// if the result doesn't match the saved turn number and outcome.
+    pub fn into_game(mut self) -> Result<Game> {
+        if self.format_version != Self::FORMAT_VERSION {
+            return Err(anyhow!(
+                "Cannot load a game saved in format version {}; only version {} is supported.",
+                self.format_version,
+                Self::FORMAT_VERSION
+            ));
+        }
+        if (1..)
+            .zip(self.players.iter())
+            .any(|(number, player)| player.number != number)
+        {
+            return Err(anyhow!(
+                "Saved game's players must be numbered 1, 2 and so on, in turn order."
+            ));
+        }
+        // games saved before players had names or colors are played with the default ones
+        for player in &mut self.players {
+            if player.name.is_empty() {
+                player.name = Player::default_name(player.number);
+            }
+            if player.color.is_none() {
+                player.color = Some(PlayerColor::default_for(player.number));
+            }
+        }
+        let computer_players = self
+            .players
+            .iter()
+            .filter_map(|player| match player.kind {
+                PlayerKind::Computer(engine) => Some((player.number, engine)),
+                PlayerKind::Human => None,
+            })
+            .collect::<Vec<_>>();
+        let mut game = Game::new(GameSettings {
+            num_rows: self.num_rows,
+            num_columns: self.num_columns,
+            win_length: Some(self.win_length),
+            marks: self.players.iter().map(|player| player.mark).collect(),
+            names: self
+                .players
+                .iter()
+                .map(|player| player.name.clone())
+                .collect(),
+            colors: self
+                .players
+                .iter()
+                .filter_map(|player| player.color)
+                .collect(),
+            first_player_number: self.first_player_number,
+            computer_player_numbers: computer_players.iter().map(|&(number, _)| number).collect(),
+            computer_engine: computer_players
+                .first()
+                .map_or(GameSettings::default().computer_engine, |&(_, engine)| {
+                    engine
+                }),
+        })?;
+        // GameSettings only allows one computer engine, but each saved player keeps their own
+        game.players = self.players;
+
+        for (move_index, unparsed_coords) in self.moves.iter().enumerate() {
+            Coordinates::from_user_input(unparsed_coords)
+                .and_then(|coords| game.play_move(coords))
+                .context(format!(
+                    "Could not replay move {} ('{}') of saved game.",
+                    move_index + 1,
+                    unparsed_coords
+                ))?;
+        }
+        // a finished game leaves its last notification behind; it shouldn't show up on load
+        game.take_notification();
+
+        if game.turn_number() != self.turn_number
+            || SavedOutcome::from_outcome(game.outcome()) != self.outcome
+        {
+            return Err(anyhow!(
+                "Saved game's turn number and outcome don't match its moves."
+            ));
+        }
+        Ok(game)
+    }
+}
+
+impl SavedOutcome {
+    fn from_outcome(outcome: GameOutcome) -> Self {
+        match outcome {
+            GameOutcome::InProgress => Self::InProgress,
+            GameOutcome::Draw => Self::Draw,
+            GameOutcome::Victory(player) => Self::Victory {
+                player_number: player.number,
+            },
+        }
+    }
+}
+
+/// Writes the game to the given file as JSON, replacing anything already there.
+pub fn save_game(game: &Game, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(&SavedGame::from_game(game))?;
+    fs::write(path, json).context(format!("Could not write to {}.", path.display()))
+}
+
+/// Reads a game saved by `save_game`.
+pub fn load_game(path: &Path) -> Result<Game> {
+    let json = fs::read_to_string(path).context(format!("Could not read {}.", path.display()))?;
+    let saved_game: SavedGame = serde_json::from_str(&json)
+        .context(format!("{} is not a valid saved game.", path.display()))?;
+    saved_game.into_game()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+    use crate::ai::{Difficulty, Engine};
+
+    fn game_after(settings: GameSettings, moves: &str) -> Game {
+        let mut game = Game::new(settings).unwrap();
+        for input in moves.split_whitespace() {
+            game.play_move(Coordinates::from_user_input(input).unwrap())
+                .unwrap();
+        }
+        game
+    }
+
+    // saves the game to a file of its own and loads it straight back
+    fn save_and_load(game: &Game, file_name: &str) -> Game {
+        let path = std::env::temp_dir().join(format!("{}-{}", process::id(), file_name));
+        save_game(game, &path).unwrap();
+        let loaded_game = load_game(&path);
+        fs::remove_file(&path).unwrap();
+        loaded_game.unwrap()
+    }
+
+    #[test]
+    fn round_trips_a_game_in_progress() {
+        let game = game_after(
+            GameSettings {
+                num_rows: 4,
+                num_columns: 5,
+                win_length: Some(3),
+                marks: vec!['X', 'O', 'V'],
+                names: vec!["Ada".to_string()],
+                first_player_number: 2,
+                ..GameSettings::default()
+            },
+            "B2 A1 C3 D4",
+        );
+        let loaded_game = save_and_load(&game, "in-progress.json");
+        assert_eq!(loaded_game.num_rows(), 4);
+        assert_eq!(loaded_game.num_columns(), 5);
+        assert_eq!(loaded_game.win_length(), 3);
+        assert_eq!(loaded_game.first_player_number(), 2);
+        assert_eq!(loaded_game.players, game.players);
+        assert_eq!(loaded_game.moves(), game.moves());
+        assert_eq!(loaded_game.turn_number(), game.turn_number());
+        assert!(loaded_game.outcome() == GameOutcome::InProgress);
+        assert_eq!(
+            loaded_game.get_current_turn_player(),
+            game.get_current_turn_player()
+        );
+    }
+
+    #[test]
+    fn round_trips_a_finished_game_against_the_computer() {
+        let engine = Engine::Minimax(Difficulty::Hard);
+        let mut game = game_after(
+            GameSettings {
+                computer_player_numbers: vec![2],
+                computer_engine: engine,
+                ..GameSettings::default()
+            },
+            "A1 B1 A2 B2 A3",
+        );
+        let mut loaded_game = save_and_load(&game, "finished.json");
+        assert_eq!(loaded_game.players[1].kind, PlayerKind::Computer(engine));
+        assert!(loaded_game.outcome() == game.outcome());
+        assert_eq!(loaded_game.moves(), game.moves());
+        // the win was announced when it happened, not when the game was loaded
+        assert!(loaded_game.take_notification().is_none());
+        assert!(game.take_notification().is_some());
+    }
+
+    #[test]
+    fn rejects_saved_games_that_do_not_add_up() {
+        let game = game_after(GameSettings::default(), "B2 A1");
+        let edits: [fn(&mut SavedGame); 4] = [
+            |saved_game| saved_game.turn_number = 2,
+            |saved_game| saved_game.outcome = SavedOutcome::Draw,
+            |saved_game| saved_game.format_version = SavedGame::FORMAT_VERSION + 1,
+            |saved_game| saved_game.players.swap(0, 1),
+        ];
+        for edit in edits {
+            let mut saved_game = SavedGame::from_game(&game);
+            edit(&mut saved_game);
+            assert!(saved_game.into_game().is_err());
+        }
+        assert!(SavedGame::from_game(&game).into_game().is_ok());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::{self, Write};
+
+use anyhow::Result;
+use colored::*;
+
+use tic_tac_toe::{Game, GameOutcome, Player};
+
+use crate::cli::PlayArgs;
+use crate::play_game;
+
+// Every player's results so far in a match of up to `best_of` games.
+struct Scoreboard {
+    best_of: usize,
+    players: Vec<Player>,
+    // indexed the same way as `players`
+    records: Vec<Record>,
+    num_games_played: usize,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Record {
+    wins: usize,
+    losses: usize,
+    draws: usize,
+}
+
+impl Scoreboard {
+    fn new(game: &Game, best_of: usize) -> Self {
+        Self {
+            best_of,
+            players: game.players.clone(),
+            records: vec![Record::default(); game.players.len()],
+            num_games_played: 0,
+        }
+    }
+
+    // Tallies a finished game; unfinished ones don't count.
+    fn record(&mut self, outcome: &GameOutcome) {
+        match outcome {
+            GameOutcome::InProgress => return,
+            GameOutcome::Draw => self.records.iter_mut().for_each(|record| record.draws += 1),
+            GameOutcome::Victory(winner) => {
+                for (player, record) in self.players.iter().zip(self.records.iter_mut()) {
+                    if player.number == winner.number {
+                        record.wins += 1;
+                    } else {
+                        record.losses += 1;
+                    }
+                }
+            }
+        }
+        self.num_games_played += 1;
+    }
+
+    // The match is over once every game has been played, or once the leader has more wins
+    // than anyone else could reach in the games that are left.
+    fn is_decided(&self) -> bool {
+        let num_games_left = self.best_of - self.num_games_played;
+        let mut wins = self
+            .records
+            .iter()
+            .map(|record| record.wins)
+            .collect::<Vec<_>>();
+        wins.sort_unstable_by(|a, b| b.cmp(a));
+        num_games_left == 0 || wins[0] > wins[1] + num_games_left
+    }
+
+    // whoever has the most wins, unless that's more than one player
+    fn leader(&self) -> Option<&Player> {
+        let most_wins = self.records.iter().map(|record| record.wins).max()?;
+        let mut leaders = self
+            .players
+            .iter()
+            .zip(&self.records)
+            .filter(|(_, record)| record.wins == most_wins);
+        match (leaders.next(), leaders.next()) {
+            (Some((player, _)), None) => Some(player),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Scoreboard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            format!(
+                "Best of {}, after {}",
+                self.best_of,
+                pluralize(self.num_games_played, "game")
+            )
+            .bold()
+        )?;
+        let name_width = self
+            .players
+            .iter()
+            .map(|player| player.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        for (player, record) in self.players.iter().zip(&self.records) {
+            // the mark is colored, so it's padded separately from the name
+            write!(
+                f,
+                "\n  {} {:<width$}  {} W  {} L  {} D",
+                player.colored_mark(),
+                player.name,
+                record.wins,
+                record.losses,
+                record.draws,
+                width = name_width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Plays a match of up to `best_of` games, starting with the given one. Each game after the
+// first is a rematch, so everyone takes turns moving first.
+pub fn play_series(mut game: Game, args: &PlayArgs, best_of: usize) -> Result<()> {
+    loop {
+        let mut scoreboard = Scoreboard::new(&game, best_of);
+        while !scoreboard.is_decided() {
+            play_game(&mut game, args, Some(&scoreboard.to_string()))?;
+            if game.outcome() == GameOutcome::InProgress {
+                // the players quit partway through a game, which ends the match along with it
+                return Ok(());
+            }
+            scoreboard.record(&game.outcome());
+            println!();
+            println!("{}", scoreboard);
+            println!();
+            if scoreboard.is_decided() {
+                break;
+            }
+            let input = prompt(&format!(
+                "Enter 'quit' to end the match here, or anything else to play game {}: ",
+                scoreboard.num_games_played + 1
+            ))?;
+            if input.is_none_or(|input| input.eq_ignore_ascii_case("quit")) {
+                return Ok(());
+            }
+            game = game.rematch();
+        }
+
+        match scoreboard.leader() {
+            Some(winner) => println!("{}", format!("{} wins the match!", winner).green().bold()),
+            None => println!("{}", "The match is a draw!".bold()),
+        }
+        let input = prompt(&format!(
+            "Enter 'rematch' to play another best of {}, or anything else to quit: ",
+            best_of
+        ))?;
+        if !input.is_some_and(|input| input.eq_ignore_ascii_case("rematch")) {
+            return Ok(());
+        }
+        game = game.rematch();
+    }
+}
+
+// Asks the question and returns the (trimmed) answer, or None if there's no more input.
+fn prompt(question: &str) -> Result<Option<String>> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
+
+fn pluralize(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tic_tac_toe::GameSettings;
+
+    use super::*;
+
+    fn new_scoreboard(best_of: usize) -> (Scoreboard, Vec<Player>) {
+        let game = Game::new(GameSettings::default()).unwrap();
+        (Scoreboard::new(&game, best_of), game.players)
+    }
+
+    #[test]
+    fn ends_early_once_nobody_can_catch_up() {
+        let (mut scoreboard, players) = new_scoreboard(3);
+        scoreboard.record(&GameOutcome::Victory(players[0].clone()));
+        assert!(!scoreboard.is_decided());
+        scoreboard.record(&GameOutcome::Victory(players[0].clone()));
+        assert!(scoreboard.is_decided());
+        assert_eq!(scoreboard.leader(), Some(&players[0]));
+
+        // one win each leaves it all on the last game
+        let (mut scoreboard, players) = new_scoreboard(3);
+        scoreboard.record(&GameOutcome::Victory(players[0].clone()));
+        scoreboard.record(&GameOutcome::Victory(players[1].clone()));
+        assert!(!scoreboard.is_decided());
+        assert_eq!(scoreboard.leader(), None);
+    }
+
+    #[test]
+    fn draws_and_unfinished_games_count_toward_no_one() {
+        let (mut scoreboard, players) = new_scoreboard(3);
+        scoreboard.record(&GameOutcome::InProgress);
+        assert_eq!(scoreboard.num_games_played, 0);
+        scoreboard.record(&GameOutcome::Draw);
+        scoreboard.record(&GameOutcome::Victory(players[1].clone()));
+        // player 2 is ahead with one game left, but player 1 could still draw level
+        assert!(!scoreboard.is_decided());
+        scoreboard.record(&GameOutcome::Draw);
+        assert!(scoreboard.is_decided());
+        assert_eq!(scoreboard.leader(), Some(&players[1]));
+        assert_eq!(scoreboard.records[0].draws, 2);
+        assert_eq!(scoreboard.records[0].losses, 1);
+    }
+
+    #[test]
+    fn even_length_matches_can_end_tied() {
+        let (mut scoreboard, players) = new_scoreboard(2);
+        scoreboard.record(&GameOutcome::Victory(players[0].clone()));
+        assert!(!scoreboard.is_decided());
+        scoreboard.record(&GameOutcome::Victory(players[1].clone()));
+        assert!(scoreboard.is_decided());
+        assert_eq!(scoreboard.leader(), None);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use tic_tac_toe::{Coordinates, Game, GameOutcome, GameSettings, MoveError, Player, PlayerColor};
+
+use crate::cli::ServeArgs;
+
+// Every game the server is hosting, by id. Games live for as long as the server does.
+#[derive(Default)]
+struct Games {
+    games: BTreeMap<u64, Game>,
+    next_game_id: u64,
+}
+
+// The body of `POST /games`. Every field is optional, defaulting to a classic 3x3 game.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct NewGameRequest {
+    size: Option<usize>,
+    rows: Option<usize>,
+    columns: Option<usize>,
+    win_length: Option<usize>,
+    marks: Option<Vec<char>>,
+    names: Vec<String>,
+    colors: Vec<PlayerColor>,
+    first_player_number: Option<u8>,
+}
+
+// The body of `POST /games/{id}/moves`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MoveRequest {
+    coordinates: String,
+}
+
+// What every successful request responds with: the game's full state after the request.
+#[derive(Serialize)]
+struct GameState<'a> {
+    id: u64,
+    num_rows: usize,
+    num_columns: usize,
+    win_length: usize,
+    players: &'a [Player],
+    // None once the game is over
+    current_player_number: Option<u8>,
+    turn_number: usize,
+    moves: Vec<String>,
+    // each tile's mark, or null for an empty tile, one row at a time from the top
+    board: Vec<Vec<Option<char>>>,
+    legal_moves: Vec<String>,
+    outcome: GameOutcome,
+}
+
+// A request that couldn't be carried out, sent back as e.g.
+// `{"error": {"code": "tile_occupied", "message": "Tile B2 is already occupied by Player 1."}}`
+struct ApiError {
+    status: u16,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, code: &'static str, message: impl Display) -> Self {
+        Self {
+            status,
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    // distinguishes the ways a move can be rejected, so clients don't have to parse messages
+    fn from_move_error(error: anyhow::Error) -> Self {
+        let (status, code) = match error.downcast_ref::<MoveError>() {
+            Some(MoveError::GameOver { .. }) => (409, "game_over"),
+            Some(MoveError::Occupied { .. }) => (409, "tile_occupied"),
+            Some(MoveError::OffBoard { .. }) => (422, "off_board"),
+            None => (422, "invalid_move"),
+        };
+        Self::new(status, code, error)
+    }
+}
+
+pub fn serve(args: ServeArgs) -> Result<()> {
+    // only this machine can reach the server; it has no authentication of any kind
+    let server = Server::http(("127.0.0.1", args.port))
+        .map_err(|error| anyhow!(error))
+        .context(format!("Could not listen on port {}.", args.port))?;
+    println!(
+        "Serving games at http://localhost:{}/games; press Ctrl-C to stop.",
+        args.port
+    );
+    let mut games = Games::default();
+    for mut request in server.incoming_requests() {
+        let (status, body) = match games.handle(&mut request) {
+            Ok((status, body)) => (status, body),
+            Err(error) => (
+                error.status,
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "error": {"code": error.code, "message": error.message}
+                }))
+                .unwrap(),
+            ),
+        };
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        // a client that hangs up before hearing back is no reason to stop serving everyone else
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+impl Games {
+    // Routes the request, returning the response's status code and JSON body.
+    fn handle(&mut self, request: &mut Request) -> Result<(u16, String), ApiError> {
+        let path = request
+            .url()
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let segments = path.trim_matches('/').split('/').collect::<Vec<&str>>();
+        let (status, game_id) = match (request.method(), segments.as_slice()) {
+            (Method::Post, ["games"]) => (201, self.create(read_json(request)?)?),
+            (Method::Get, ["games", game_id]) => (200, self.find(game_id)?),
+            (Method::Post, ["games", game_id, "moves"]) => {
+                let game_id = self.find(game_id)?;
+                let move_request = read_json(request)?;
+                (200, self.play_move(game_id, move_request)?)
+            }
+            (_, ["games"] | ["games", _] | ["games", _, "moves"]) => {
+                return Err(ApiError::new(
+                    405,
+                    "method_not_allowed",
+                    format!("{} is not supported for {}.", request.method(), path),
+                ))
+            }
+            _ => {
+                return Err(ApiError::new(
+                    404,
+                    "not_found",
+                    format!("There is nothing at {}.", path),
+                ))
+            }
+        };
+        Ok((status, self.state_json(game_id)))
+    }
+
+    fn create(&mut self, new_game: NewGameRequest) -> Result<u64, ApiError> {
+        let defaults = GameSettings::default();
+        let size = new_game.size.unwrap_or(defaults.num_rows);
+        let game = Game::new(GameSettings {
+            num_rows: new_game.rows.unwrap_or(size),
+            num_columns: new_game.columns.unwrap_or(size),
+            win_length: new_game.win_length,
+            marks: new_game.marks.unwrap_or(defaults.marks),
+            names: new_game.names,
+            colors: new_game.colors,
+            first_player_number: new_game
+                .first_player_number
+                .unwrap_or(defaults.first_player_number),
+            ..GameSettings::default()
+        })
+        .map_err(|error| ApiError::new(422, "invalid_settings", error))?;
+        self.next_game_id += 1;
+        self.games.insert(self.next_game_id, game);
+        Ok(self.next_game_id)
+    }
+
+    // the id of the game with the given (unparsed) id, if there is one
+    fn find(&self, unparsed_game_id: &str) -> Result<u64, ApiError> {
+        unparsed_game_id
+            .parse()
+            .ok()
+            .filter(|game_id| self.games.contains_key(game_id))
+            .ok_or_else(|| {
+                ApiError::new(
+                    404,
+                    "game_not_found",
+                    format!("There is no game {}.", unparsed_game_id),
+                )
+            })
+    }
+
+    fn play_move(&mut self, game_id: u64, move_request: MoveRequest) -> Result<u64, ApiError> {
+        let game = self.games.get_mut(&game_id).unwrap(); // safe thanks to `find`
+        let coords = Coordinates::from_user_input(&move_request.coordinates)
+            .map_err(|error| ApiError::new(422, "invalid_coordinates", error))?;
+        game.play_move(coords).map_err(ApiError::from_move_error)?;
+        // the API's clients render the game themselves, so the notification would go unseen
+        game.take_notification();
+        Ok(game_id)
+    }
+
+    fn state_json(&self, game_id: u64) -> String {
+        let game = &self.games[&game_id];
+        let state = GameState {
+            id: game_id,
+            num_rows: game.num_rows(),
+            num_columns: game.num_columns(),
+            win_length: game.win_length(),
+            players: &game.players,
+            current_player_number: (game.outcome() == GameOutcome::InProgress)
+                .then(|| game.get_current_turn_player().number),
+            turn_number: game.turn_number(),
+            moves: game.moves().iter().map(Coordinates::to_string).collect(),
+            board: game
+                .tiles()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|tile| tile.as_ref().map(|player| player.mark))
+                        .collect()
+                })
+                .collect(),
+            legal_moves: game
+                .legal_moves()
+                .iter()
+                .map(Coordinates::to_string)
+                .collect(),
+            outcome: game.outcome(),
+        };
+        serde_json::to_string_pretty(&state).unwrap() // plain data, so serializing can't fail
+    }
+}
+
+// Parses the request's body as JSON; an empty body counts as `{}`.
+fn read_json<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|error| ApiError::new(400, "unreadable_body", error))?;
+    let body = if body.trim().is_empty() { "{}" } else { &body };
+    serde_json::from_str(body).map_err(|error| {
+        ApiError::new(
+            400,
+            "invalid_json",
+            format!("Could not parse request body: {}.", error),
+        )
+    })
+}
@@ -0,0 +1,305 @@
+//! Keeping track of every finished game, to rank players across them.
+//!
+//! Results are kept by player name in a JSON file in the user's data directory (see
+//! `default_path`), oldest first. For example:
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "games": [
+//!     { "finished_at": "2026-10-17T20:15:03+02:00", "players": ["Ada", "Grace"], "winner": "Ada" },
+//!     { "finished_at": "2026-10-17T20:18:41+02:00", "players": ["Grace", "Ada"], "winner": null }
+//!   ]
+//! }
+//! ```
+//!
+//! Only the results are kept; totals and ratings are worked out from them whenever they're
+//! needed, so tweaking the rating system rates every game again.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, GameOutcome};
+
+/// Every game recorded so far.
+#[derive(Serialize, Deserialize)]
+pub struct Stats {
+    /// Bumped whenever the format changes in a way older versions of the game can't read.
+    pub format_version: u32,
+    /// Oldest first.
+    pub games: Vec<GameResult>,
+}
+
+/// How one finished game turned out.
+#[derive(Serialize, Deserialize)]
+pub struct GameResult {
+    /// When the game ended, in RFC 3339 format.
+    pub finished_at: String,
+    /// Everyone who played, in turn order.
+    pub players: Vec<String>,
+    /// None for a draw.
+    pub winner: Option<String>,
+}
+
+/// One player's line on the leaderboard.
+#[derive(Clone, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// An Elo rating, starting from `Stats::INITIAL_RATING`.
+    pub rating: f64,
+}
+
+impl Standing {
+    /// How many games the player has finished.
+    pub fn num_games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            games: vec![],
+        }
+    }
+}
+
+impl Stats {
+    /// The version of the stats file's format; files of any other version are rejected.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Every player's rating before their first game.
+    pub const INITIAL_RATING: f64 = 1500.0;
+
+    // How far a single game can move a rating. With more than two players, it's shared out
+    // between the pairings that make up the game (see `standings`).
+    const K_FACTOR: f64 = 32.0;
+
+    /// Adds the game's result, failing if it isn't over yet.
+    pub fn record(&mut self, game: &Game) -> Result<()> {
+        let winner = match game.outcome() {
+            GameOutcome::InProgress => {
+                return Err(anyhow!("Cannot record the stats of an unfinished game."))
+            }
+            GameOutcome::Draw => None,
+            GameOutcome::Victory(player) => Some(player.name),
+        };
+        self.games.push(GameResult {
+            finished_at: chrono::Local::now().to_rfc3339(),
+            players: game
+                .players
+                .iter()
+                .map(|player| player.name.clone())
+                .collect(),
+            winner,
+        });
+        Ok(())
+    }
+
+    /// Everyone who has played, best rated first.
+    ///
+    /// Ratings are Elo ratings, worked out game by game in the order they were played. A game
+    /// with more than two players counts as a match between every pair of them, with the
+    /// winner beating everyone else and everyone else drawing with each other, and each of
+    /// those pairings moving ratings by a share of the usual amount.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = vec![];
+        let mut indices_by_name: HashMap<&str, usize> = HashMap::new();
+        for game in &self.games {
+            let player_indices = game
+                .players
+                .iter()
+                .map(|name| {
+                    *indices_by_name.entry(name).or_insert_with(|| {
+                        standings.push(Standing {
+                            name: name.clone(),
+                            wins: 0,
+                            draws: 0,
+                            losses: 0,
+                            rating: Self::INITIAL_RATING,
+                        });
+                        standings.len() - 1
+                    })
+                })
+                .collect::<Vec<usize>>();
+            // one player's score against another: 1 for beating them, 0 for losing to them, and
+            // 0.5 otherwise
+            let score = |name: &str, opponent_name: &str| match &game.winner {
+                Some(winner) if winner == name => 1.0,
+                Some(winner) if winner == opponent_name => 0.0,
+                _ => 0.5,
+            };
+            let k_factor = Self::K_FACTOR / (player_indices.len().max(2) - 1) as f64;
+            // every rating changes based on everyone's rating from before the game
+            let rating_changes = player_indices
+                .iter()
+                .map(|&index| {
+                    player_indices
+                        .iter()
+                        .filter(|&&opponent_index| opponent_index != index)
+                        .map(|&opponent_index| {
+                            let actual =
+                                score(&standings[index].name, &standings[opponent_index].name);
+                            let expected = expected_score(
+                                standings[index].rating,
+                                standings[opponent_index].rating,
+                            );
+                            k_factor * (actual - expected)
+                        })
+                        .sum::<f64>()
+                })
+                .collect::<Vec<f64>>();
+            for (&index, rating_change) in player_indices.iter().zip(rating_changes) {
+                let standing = &mut standings[index];
+                standing.rating += rating_change;
+                match &game.winner {
+                    None => standing.draws += 1,
+                    Some(winner) if *winner == standing.name => standing.wins += 1,
+                    Some(_) => standing.losses += 1,
+                }
+            }
+        }
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        standings
+    }
+}
+
+// the chance (counting a draw as half a win) that a player with the given rating beats one
+// with the opponent's rating, according to Elo
+fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+/// Where stats are kept unless told otherwise: `tic-tac-toe/stats.json` in the user's data
+/// directory, e.g. `~/.local/share` on Linux.
+pub fn default_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().context("Could not find a data directory to keep stats in.")?;
+    Ok(data_dir.join("tic-tac-toe").join("stats.json"))
+}
+
+/// Reads the stats in the given file; if there's no such file yet, no games have been recorded.
+pub fn load_stats(path: &Path) -> Result<Stats> {
+    if !path.exists() {
+        return Ok(Stats::default());
+    }
+    let json = fs::read_to_string(path).context(format!("Could not read {}.", path.display()))?;
+    let stats: Stats = serde_json::from_str(&json)
+        .context(format!("{} is not a valid stats file.", path.display()))?;
+    if stats.format_version != Stats::FORMAT_VERSION {
+        return Err(anyhow!(
+            "Cannot read stats in format version {}; only version {} is supported.",
+            stats.format_version,
+            Stats::FORMAT_VERSION
+        ));
+    }
+    Ok(stats)
+}
+
+/// Adds the finished game's result to the stats in the given file, creating it if need be.
+pub fn record_game(game: &Game, path: &Path) -> Result<()> {
+    let mut stats = load_stats(path)?;
+    stats.record(game)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context(format!("Could not create {}.", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(&stats)?;
+    fs::write(path, json).context(format!("Could not write to {}.", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(games: &[(&[&str], Option<&str>)]) -> Stats {
+        Stats {
+            games: games
+                .iter()
+                .map(|&(players, winner)| GameResult {
+                    finished_at: String::new(),
+                    players: players.iter().map(|name| name.to_string()).collect(),
+                    winner: winner.map(String::from),
+                })
+                .collect(),
+            ..Stats::default()
+        }
+    }
+
+    fn standing<'a>(standings: &'a [Standing], name: &str) -> &'a Standing {
+        standings
+            .iter()
+            .find(|standing| standing.name == name)
+            .unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn a_win_between_equals_moves_both_ratings_by_half_the_k_factor() {
+        let standings = stats(&[(&["Ada", "Grace"], Some("Ada"))]).standings();
+        assert_eq!(standings[0].name, "Ada");
+        assert_close(standings[0].rating, 1516.0);
+        assert_close(standings[1].rating, 1484.0);
+    }
+
+    #[test]
+    fn a_draw_between_equals_leaves_ratings_alone_and_favors_the_underdog_otherwise() {
+        let standings = stats(&[(&["Ada", "Grace"], None)]).standings();
+        assert_close(standing(&standings, "Ada").rating, 1500.0);
+        assert_close(standing(&standings, "Grace").rating, 1500.0);
+
+        let standings =
+            stats(&[(&["Ada", "Grace"], Some("Ada")), (&["Ada", "Grace"], None)]).standings();
+        let ada_rating = standing(&standings, "Ada").rating;
+        assert!(ada_rating < 1516.0 && ada_rating > 1500.0);
+        // ratings only ever move from one player to another
+        assert_close(ada_rating + standing(&standings, "Grace").rating, 3000.0);
+    }
+
+    #[test]
+    fn games_with_more_players_share_out_the_k_factor() {
+        let standings = stats(&[(&["Ada", "Grace", "Alan"], Some("Ada"))]).standings();
+        // Ada beats both others, each pairing worth half as much as a two-player game, and the
+        // other two draw with each other
+        assert_close(standing(&standings, "Ada").rating, 1516.0);
+        assert_close(standing(&standings, "Grace").rating, 1492.0);
+        assert_close(standing(&standings, "Alan").rating, 1492.0);
+    }
+
+    #[test]
+    fn totals_every_players_wins_draws_and_losses() {
+        let standings = stats(&[
+            (&["Ada", "Grace"], Some("Ada")),
+            (&["Grace", "Ada"], None),
+            (&["Grace", "Alan"], Some("Alan")),
+            (&["Ada", "Grace", "Alan"], Some("Grace")),
+        ])
+        .standings();
+        let totals = |name| {
+            let standing = standing(&standings, name);
+            (standing.wins, standing.draws, standing.losses)
+        };
+        assert_eq!(totals("Ada"), (1, 1, 1));
+        assert_eq!(totals("Grace"), (1, 1, 2));
+        assert_eq!(totals("Alan"), (1, 0, 1));
+        assert_eq!(standing(&standings, "Grace").num_games(), 4);
+        // best rated first
+        assert!(standings
+            .windows(2)
+            .all(|pair| pair[0].rating >= pair[1].rating));
+    }
+}
@@ -0,0 +1,478 @@
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+
+use tic_tac_toe::controller::{self, PlayerController};
+use tic_tac_toe::{Game, GameOutcome, GameSettings, Notification, NotificationType};
+
+use crate::cli::{TournamentArgs, TournamentFormat};
+use crate::leaderboard::record_stats;
+use crate::show_game_with_header;
+
+// A tournament in progress: who's taking part, and how each of them is doing.
+struct Tournament<'a> {
+    args: &'a TournamentArgs,
+    // indexed the same way as `args.participants`, which is also their seeding
+    standings: Vec<Standing>,
+}
+
+#[derive(Default, Clone)]
+struct Standing {
+    // counted in halves, since a draw is worth half a point
+    half_points: usize,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    byes: usize,
+    // the index of everyone they've been paired with so far
+    opponents: Vec<usize>,
+}
+
+impl Standing {
+    fn points(&self) -> f64 {
+        self.half_points as f64 / 2.0
+    }
+}
+
+// Pairs up participants (by index) for each round, and says who sits the round out, if anyone.
+type Round = (Vec<(usize, usize)>, Option<usize>);
+
+// Plays every pairing of the tournament in turn, round by round, then prints the final
+// standings. A win is worth a point and a draw half a point. Games between two computer
+// players are played out without showing the board, so bots can be pitted against each other
+// quickly.
+pub fn run(args: TournamentArgs) -> Result<()> {
+    let num_participants = args.participants.len();
+    if num_participants < 2 {
+        return Err(anyhow!(
+            "A tournament needs at least two participants; add them with --participant."
+        ));
+    }
+    for (index, participant) in args.participants.iter().enumerate() {
+        if args.participants[..index]
+            .iter()
+            .any(|other| other.name == participant.name)
+        {
+            return Err(anyhow!(
+                "Participants must have different names, but more than one is called '{}'.",
+                participant.name
+            ));
+        }
+    }
+    if args.rounds.is_some() && args.format != TournamentFormat::Swiss {
+        return Err(anyhow!("--rounds only applies to Swiss tournaments."));
+    }
+    // fail now, rather than after the first pairing has sat down to play
+    Game::new(args.board.game_settings())?;
+
+    let mut tournament = Tournament {
+        args: &args,
+        standings: vec![Standing::default(); num_participants],
+    };
+    let round_robin_rounds = round_robin_rounds(num_participants);
+    let num_rounds = match args.format {
+        TournamentFormat::RoundRobin => round_robin_rounds.len(),
+        // enough rounds for one participant to be the only one who's won every round
+        TournamentFormat::Swiss => args.rounds.map_or_else(
+            || (usize::BITS - (num_participants - 1).leading_zeros()) as usize,
+            usize::from,
+        ),
+    };
+    for round_number in 1..=num_rounds {
+        let (pairings, sitting_out) = match args.format {
+            TournamentFormat::RoundRobin => round_robin_rounds[round_number - 1].clone(),
+            TournamentFormat::Swiss => tournament.swiss_round(),
+        };
+        let round_label = format!("Round {} of {}", round_number, num_rounds);
+        println!();
+        println!("{}", round_label.bold());
+        if let Some(index) = sitting_out {
+            tournament.give_bye(index);
+            println!("{} sits this round out.", args.participants[index].name);
+        }
+        for (index, opponent_index) in pairings {
+            tournament.play_pairing(&round_label, index, opponent_index)?;
+        }
+    }
+
+    println!();
+    println!("{}", "Final standings".bold());
+    println!("{}", tournament.table());
+    Ok(())
+}
+
+impl Tournament<'_> {
+    // Plays every game between the two participants, the first of them moving first in the
+    // first game, and tallies the results.
+    fn play_pairing(
+        &mut self,
+        round_label: &str,
+        index: usize,
+        opponent_index: usize,
+    ) -> Result<()> {
+        let participants = [
+            &self.args.participants[index],
+            &self.args.participants[opponent_index],
+        ];
+        let mut game = Game::new(GameSettings {
+            names: participants
+                .iter()
+                .map(|participant| participant.name.clone())
+                .collect(),
+            ..self.args.board.game_settings()
+        })?;
+        for (player, participant) in game.players.iter_mut().zip(participants) {
+            player.kind = participant.kind;
+        }
+        self.standings[index].opponents.push(opponent_index);
+        self.standings[opponent_index].opponents.push(index);
+
+        let num_games = usize::from(self.args.games_per_pairing);
+        for game_number in 1..=num_games {
+            if game_number > 1 {
+                game = game.rematch();
+            }
+            let game_label = format!(
+                "{}: {} vs {}, game {} of {}",
+                round_label, participants[0].name, participants[1].name, game_number, num_games
+            );
+            let mut controllers = participants
+                .iter()
+                .map(|participant| controller::controller_for(participant.kind))
+                .collect::<Vec<_>>();
+            if controllers.iter().any(|controller| controller.is_person()) {
+                let header = format!("{}\n\n{}", self.table(), game_label.bold());
+                play_in_person(&mut game, &mut controllers, &header)?;
+            } else {
+                // nobody's watching, so there's no need to show each move
+                controller::play_out(&mut game, &mut controllers)?;
+                game.take_notification();
+            }
+
+            let outcome = game.outcome();
+            let result = match &outcome {
+                GameOutcome::Victory(winner) => {
+                    let loser = game
+                        .players
+                        .iter()
+                        .find(|player| player.number != winner.number)
+                        .unwrap(); // safe, since there are two players
+                    format!("{} beat {}.", winner, loser)
+                }
+                _ => format!(
+                    "{} and {} drew.",
+                    participants[0].name, participants[1].name
+                ),
+            };
+            println!("{}: {}", game_label, result);
+            self.tally(index, opponent_index, &outcome);
+            record_stats(&game, &self.args.stats)?;
+        }
+        Ok(())
+    }
+
+    // Adds a finished game between the two participants (the first of whom played player 1) to
+    // their standings.
+    fn tally(&mut self, index: usize, opponent_index: usize, outcome: &GameOutcome) {
+        let (winner, loser) = match outcome {
+            GameOutcome::InProgress => return,
+            GameOutcome::Draw => {
+                for index in [index, opponent_index] {
+                    self.standings[index].draws += 1;
+                    self.standings[index].half_points += 1;
+                }
+                return;
+            }
+            GameOutcome::Victory(player) if player.number == 1 => (index, opponent_index),
+            GameOutcome::Victory(_) => (opponent_index, index),
+        };
+        self.standings[winner].wins += 1;
+        self.standings[winner].half_points += 2;
+        self.standings[loser].losses += 1;
+    }
+
+    // A Swiss bye counts as winning every game of the pairing; round-robin byes are just
+    // rest, since everyone gets one.
+    fn give_bye(&mut self, index: usize) {
+        let standing = &mut self.standings[index];
+        standing.byes += 1;
+        if self.args.format == TournamentFormat::Swiss {
+            standing.half_points += 2 * usize::from(self.args.games_per_pairing);
+        }
+    }
+
+    // Everyone's index, best placed first: by points, then by wins, then by seeding.
+    fn ranking(&self) -> Vec<usize> {
+        let mut ranking = (0..self.standings.len()).collect::<Vec<usize>>();
+        ranking.sort_by_key(|&index| {
+            let standing = &self.standings[index];
+            (
+                std::cmp::Reverse(standing.half_points),
+                std::cmp::Reverse(standing.wins),
+            )
+        });
+        ranking
+    }
+
+    // Pairs each participant, from the top of the standings down, with the best-placed
+    // participant they haven't played yet, or simply the next best-placed if they've played
+    // everyone left. With an odd number of participants, the lowest-placed one who hasn't
+    // had a bye yet sits out.
+    fn swiss_round(&self) -> Round {
+        let mut unpaired = self.ranking();
+        let sitting_out = (unpaired.len() % 2 == 1).then(|| {
+            let position = unpaired
+                .iter()
+                .rposition(|&index| self.standings[index].byes == 0)
+                .unwrap_or(unpaired.len() - 1);
+            unpaired.remove(position)
+        });
+        let mut pairings = vec![];
+        while !unpaired.is_empty() {
+            let index = unpaired.remove(0);
+            let position = unpaired
+                .iter()
+                .position(|opponent_index| {
+                    !self.standings[index].opponents.contains(opponent_index)
+                })
+                .unwrap_or(0);
+            pairings.push((index, unpaired.remove(position)));
+        }
+        (pairings, sitting_out)
+    }
+
+    fn table(&self) -> String {
+        let name_width = self
+            .args
+            .participants
+            .iter()
+            .map(|participant| participant.name.chars().count())
+            .chain(["Participant".len()])
+            .max()
+            .unwrap_or_default();
+        let mut table = format!(
+            "{:>4}  {:<width$}  {:>6}  {:>4}  {:>4}  {:>4}",
+            "Rank",
+            "Participant",
+            "Points",
+            "W",
+            "D",
+            "L",
+            width = name_width
+        )
+        .bold()
+        .to_string();
+        for (rank, index) in (1..).zip(self.ranking()) {
+            let standing = &self.standings[index];
+            // writing to a String can't fail
+            let _ = write!(
+                table,
+                "\n{:>4}  {:<width$}  {:>6}  {:>4}  {:>4}  {:>4}",
+                rank,
+                self.args.participants[index].name,
+                standing.points(),
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                width = name_width
+            );
+        }
+        table
+    }
+}
+
+// Plays a tournament game with people at the keyboard, showing the board (under the given
+// header) before every move. Unlike a casual game, nobody can undo, redo or save moves: a
+// game's result counts toward the standings, so people only get to choose their moves.
+fn play_in_person(
+    game: &mut Game,
+    controllers: &mut [Box<dyn PlayerController>],
+    header: &str,
+) -> Result<()> {
+    while game.outcome() == GameOutcome::InProgress {
+        show_game_with_header(game, Some(header))?;
+        let current_player = game.get_current_turn_player();
+        let controller = &mut controllers[usize::from(current_player.number - 1)];
+        let coords = controller.choose_move(game)?;
+        game.play_move(coords).context(format!(
+            "{}'s controller chose an illegal move.",
+            current_player
+        ))?;
+        // if the move ended the game, the game's own notification says so instead
+        if !controller.is_person() && game.outcome() == GameOutcome::InProgress {
+            game.set_notification(Notification {
+                message: format!("{} played {}.", current_player, coords),
+                notification_type: NotificationType::Info,
+            });
+        }
+    }
+    // show the final board until the players are ready for the next game
+    show_game_with_header(game, Some(header))?;
+    print!("Press Enter to carry on: ");
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut String::new())?;
+    Ok(())
+}
+
+// Schedules a round robin by the circle method: one participant stays put while everyone else
+// rotates around them, so that everyone meets everyone else exactly once. With an odd number
+// of participants, whoever would be paired with the empty seat sits the round out.
+fn round_robin_rounds(num_participants: usize) -> Vec<Round> {
+    let mut seats = (0..num_participants).map(Some).collect::<Vec<_>>();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+    let num_seats = seats.len();
+    (1..num_seats)
+        .map(|_| {
+            let mut pairings = vec![];
+            let mut sitting_out = None;
+            for seat_index in 0..num_seats / 2 {
+                match (seats[seat_index], seats[num_seats - 1 - seat_index]) {
+                    (Some(index), Some(opponent_index)) => pairings.push((index, opponent_index)),
+                    (Some(index), None) | (None, Some(index)) => sitting_out = Some(index),
+                    (None, None) => {}
+                }
+            }
+            seats[1..].rotate_right(1);
+            (pairings, sitting_out)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tic_tac_toe::PlayerKind;
+
+    use crate::cli::{BoardArgs, Participant, RecordStatsArgs};
+
+    fn tournament_args(num_participants: usize) -> TournamentArgs {
+        TournamentArgs {
+            participants: (1..=num_participants)
+                .map(|number| Participant {
+                    name: format!("P{}", number),
+                    kind: PlayerKind::Human,
+                })
+                .collect(),
+            format: TournamentFormat::Swiss,
+            rounds: None,
+            games_per_pairing: 1,
+            board: BoardArgs {
+                size: 3,
+                rows: None,
+                columns: None,
+                win_length: None,
+            },
+            stats: RecordStatsArgs {
+                no_stats: true,
+                stats_file: None,
+            },
+        }
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_exactly_once() {
+        for num_participants in 2..=9 {
+            let rounds = round_robin_rounds(num_participants);
+            let mut num_meetings = vec![vec![0; num_participants]; num_participants];
+            for (pairings, _) in &rounds {
+                let mut seen = vec![];
+                for &(index, opponent_index) in pairings {
+                    num_meetings[index][opponent_index] += 1;
+                    num_meetings[opponent_index][index] += 1;
+                    seen.extend([index, opponent_index]);
+                }
+                // nobody plays twice in one round
+                seen.sort_unstable();
+                seen.dedup();
+                assert_eq!(seen.len(), 2 * pairings.len());
+            }
+            for (index, row) in num_meetings.iter().enumerate() {
+                for (opponent_index, &count) in row.iter().enumerate() {
+                    assert_eq!(count, usize::from(index != opponent_index));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_robin_byes_rotate_through_everyone() {
+        for num_participants in [3, 5, 7] {
+            let rounds = round_robin_rounds(num_participants);
+            assert_eq!(rounds.len(), num_participants);
+            let mut sitting_out = rounds
+                .iter()
+                .map(|(_, sitting_out)| sitting_out.unwrap())
+                .collect::<Vec<usize>>();
+            sitting_out.sort_unstable();
+            assert_eq!(sitting_out, (0..num_participants).collect::<Vec<_>>());
+        }
+        let rounds = round_robin_rounds(6);
+        assert_eq!(rounds.len(), 5);
+        assert!(rounds.iter().all(|(_, sitting_out)| sitting_out.is_none()));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches_when_it_can() {
+        let args = tournament_args(4);
+        let mut tournament = Tournament {
+            args: &args,
+            standings: vec![Standing::default(); 4],
+        };
+        for _ in 0..3 {
+            let (pairings, sitting_out) = tournament.swiss_round();
+            assert_eq!(sitting_out, None);
+            assert_eq!(pairings.len(), 2);
+            for (index, opponent_index) in pairings {
+                assert!(!tournament.standings[index]
+                    .opponents
+                    .contains(&opponent_index));
+                tournament.standings[index].opponents.push(opponent_index);
+                tournament.standings[opponent_index].opponents.push(index);
+                // whoever's placed higher wins, so the standings spread out
+                tournament.standings[index].half_points += 2;
+            }
+        }
+    }
+
+    #[test]
+    fn swiss_pairs_leaders_together() {
+        let args = tournament_args(4);
+        let mut tournament = Tournament {
+            args: &args,
+            standings: vec![Standing::default(); 4],
+        };
+        tournament.standings[3].half_points = 4;
+        tournament.standings[1].half_points = 2;
+        let (pairings, _) = tournament.swiss_round();
+        assert_eq!(pairings, vec![(3, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn swiss_byes_go_to_the_lowest_placed_without_one() {
+        let args = tournament_args(5);
+        let mut tournament = Tournament {
+            args: &args,
+            standings: vec![Standing::default(); 5],
+        };
+        let mut byes = vec![];
+        for _ in 0..5 {
+            let (pairings, sitting_out) = tournament.swiss_round();
+            assert_eq!(pairings.len(), 2);
+            let sitting_out = sitting_out.unwrap();
+            tournament.give_bye(sitting_out);
+            byes.push(sitting_out);
+        }
+        byes.sort_unstable();
+        assert_eq!(byes, vec![0, 1, 2, 3, 4]);
+        // each bye counts as winning the pairing's single game
+        assert!(tournament
+            .standings
+            .iter()
+            .all(|standing| standing.byes == 1 && standing.half_points == 2));
+    }
+}
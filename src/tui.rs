@@ -0,0 +1,276 @@
+use std::io::{self, Write};
+
+use anyhow::{anyhow, Result};
+use colored::*;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use tic_tac_toe::controller::{PlayerController, Turn};
+use tic_tac_toe::{Coordinates, Game, GameOutcome};
+
+use crate::{step_through_history, take_turn};
+
+// Puts the terminal into raw mode on a screen of its own, with mouse clicks reported as
+// events, restoring it when dropped -- even if the game ends in an error.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// What a key press asks for.
+enum Action {
+    MoveCursor { row_step: isize, column_step: isize },
+    PlaceMark,
+    ClickTile(Coordinates),
+    Undo,
+    Redo,
+    Save,
+    Quit,
+}
+
+// Plays the game in a full-screen interface, asking each player's controller (at the same index
+// in `controllers` as the player in `Game::players`) what to do with their turn; people play
+// through a `KeyboardController`. Returns when the players quit, whether or not the game is
+// over, so they get a chance to undo the final move. The header, if any, is shown above the
+// board, as in `play_game`.
+pub fn play(
+    game: &mut Game,
+    controllers: &mut [Box<dyn PlayerController>],
+    header: Option<&str>,
+) -> Result<()> {
+    let raw_terminal = RawTerminal::enter()?;
+    let header_lines = header_lines(header);
+    loop {
+        if game.outcome() == GameOutcome::InProgress {
+            let current_player = game.get_current_turn_player();
+            // people draw the board themselves while they pick a move, but nobody else does
+            if !controllers[usize::from(current_player.number - 1)].is_person() {
+                draw(game, &header_lines, None, "Thinking...")?;
+            }
+            if !take_turn(game, controllers)? {
+                break;
+            }
+            continue;
+        }
+
+        let next_step = if header.is_some() { "carry on" } else { "quit" };
+        let help = format!("Press u to take back the last move, or q to {}.", next_step);
+        draw(game, &header_lines, None, &help)?;
+        match read_action(game, header_lines.len() as u16)? {
+            Some(Action::Undo) => {
+                game.take_notification();
+                step_through_history(game, controllers, Game::undo, "Took back");
+            }
+            Some(Action::Quit) => break,
+            _ => {}
+        }
+    }
+    drop(raw_terminal);
+
+    // leave the final board behind in the normal terminal, where it won't disappear
+    println!();
+    println!("{}", game.render_board());
+    if game.outcome() != GameOutcome::InProgress {
+        if let Some(notification) = game.take_notification() {
+            println!("{}", notification);
+        }
+    }
+    Ok(())
+}
+
+// A person playing in the full-screen interface: the arrow keys move a cursor around the
+// board, and Enter places their mark under it (as does clicking a tile).
+pub struct KeyboardController {
+    header_lines: Vec<String>,
+    // the row and column of the tile under the cursor, once the board's size is known
+    cursor: Option<(usize, usize)>,
+}
+
+impl KeyboardController {
+    pub fn new(header: Option<&str>) -> Self {
+        Self {
+            header_lines: header_lines(header),
+            cursor: None,
+        }
+    }
+}
+
+impl PlayerController for KeyboardController {
+    fn choose_move(&mut self, game: &Game) -> Result<Coordinates> {
+        loop {
+            match self.take_turn(game)? {
+                Turn::Move(coords) => return Ok(coords),
+                Turn::Quit => return Err(anyhow!("Quit before the game was over.")),
+                _ => {}
+            }
+        }
+    }
+
+    fn take_turn(&mut self, game: &Game) -> Result<Turn> {
+        // start in the middle of the board, where the best opening moves usually are
+        let cursor = self
+            .cursor
+            .get_or_insert((game.num_rows() / 2, game.num_columns() / 2));
+        let current_player = game.get_current_turn_player();
+        let help = format!(
+            "{}: arrow keys move, Enter places your {}. u/r undo and redo, s saves, q quits.",
+            current_player.to_string().bold(),
+            current_player.colored_mark()
+        );
+        loop {
+            let cursor_coords = Coordinates::from_row_and_column(cursor.0, cursor.1)?;
+            // unlike the line-by-line interface, the screen is redrawn every time the cursor
+            // moves, so the game's notification is left for the next redraw
+            draw(game, &self.header_lines, Some(cursor_coords), &help)?;
+            let Some(action) = read_action(game, self.header_lines.len() as u16)? else {
+                continue;
+            };
+            return Ok(match action {
+                Action::MoveCursor {
+                    row_step,
+                    column_step,
+                } => {
+                    // stop at the edges of the board
+                    cursor.0 = cursor
+                        .0
+                        .saturating_add_signed(row_step)
+                        .min(game.num_rows() - 1);
+                    cursor.1 = cursor
+                        .1
+                        .saturating_add_signed(column_step)
+                        .min(game.num_columns() - 1);
+                    continue;
+                }
+                Action::PlaceMark => Turn::Move(cursor_coords),
+                Action::ClickTile(coords) => {
+                    // the cursor follows the mouse, so the keyboard picks up where the click left off
+                    *cursor = coords.row_and_column().unwrap_or(*cursor);
+                    Turn::Move(coords)
+                }
+                Action::Undo => Turn::Undo,
+                Action::Redo => Turn::Redo,
+                Action::Save => Turn::Save(None),
+                Action::Quit => Turn::Quit,
+            });
+        }
+    }
+
+    fn is_person(&self) -> bool {
+        true
+    }
+}
+
+// Everything above the board: a blank line, plus the header if there is one.
+fn header_lines(header: Option<&str>) -> Vec<String> {
+    let mut header_lines = vec![String::new()];
+    if let Some(header) = header {
+        header_lines.extend(header.lines().map(String::from));
+        header_lines.push(String::new());
+    }
+    header_lines
+}
+
+// Redraws the whole screen: the header, the board (with the cursor, if any) below it, the
+// game's notification, and a line of help below them.
+fn draw(game: &Game, header: &[String], cursor: Option<Coordinates>, help: &str) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut lines = header.to_vec();
+    lines.extend(
+        game.render_board_with_cursor(cursor)
+            .lines()
+            .map(String::from),
+    );
+    lines.push(String::new());
+    if let Some(notification) = game.notification() {
+        lines.push(notification.to_string());
+        lines.push(String::new());
+    }
+    lines.push(help.to_string());
+    // raw mode doesn't turn "\n" into a new line, so each line is placed explicitly
+    for (line_index, line) in lines.iter().enumerate() {
+        queue!(
+            stdout,
+            MoveTo(0, line_index as u16),
+            Clear(ClearType::CurrentLine)
+        )?;
+        write!(stdout, "{}", line)?;
+    }
+    queue!(stdout, Clear(ClearType::FromCursorDown))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+// Waits for the next key press or click, returning what it asks for (or None if it doesn't ask
+// for anything). The board is drawn starting from `board_top_line`, so clicks can be mapped
+// onto it.
+fn read_action(game: &Game, board_top_line: u16) -> Result<Option<Action>> {
+    let (code, modifiers) = match event::read()? {
+        Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) => (code, modifiers),
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            ..
+        }) => {
+            let coords = row
+                .checked_sub(board_top_line)
+                .and_then(|line| game.coordinates_at(line.into(), column.into()));
+            return Ok(coords.map(Action::ClickTile));
+        }
+        _ => return Ok(None),
+    };
+    let action = match code {
+        KeyCode::Up | KeyCode::Char('k') => Action::MoveCursor {
+            row_step: -1,
+            column_step: 0,
+        },
+        KeyCode::Down | KeyCode::Char('j') => Action::MoveCursor {
+            row_step: 1,
+            column_step: 0,
+        },
+        KeyCode::Left | KeyCode::Char('h') => Action::MoveCursor {
+            row_step: 0,
+            column_step: -1,
+        },
+        KeyCode::Right | KeyCode::Char('l') => Action::MoveCursor {
+            row_step: 0,
+            column_step: 1,
+        },
+        KeyCode::Enter | KeyCode::Char(' ') => Action::PlaceMark,
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('r') => Action::Redo,
+        KeyCode::Char('s') => Action::Save,
+        // raw mode stops Ctrl-C from interrupting the game, so it has to be handled here
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        _ => return Ok(None),
+    };
+    Ok(Some(action))
+}